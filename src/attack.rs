@@ -0,0 +1,210 @@
+use bevy::{color::palettes::css::{ORANGE, RED, YELLOW}, math::bounding::IntersectsVolume, prelude::*};
+
+use crate::{behaviour::Archetype, bounding::Volume, damage::{DamageEvent, DamageType}, enemies::{move_enemies, Death, Knockback, NearEnemy}, kinematics::Velocity, perception::Perception, player::{nearest_player, Player}, status::{StatusEffects, StatusKind}, AppState, RunState};
+
+pub struct AttackPlugin;
+
+impl Plugin for AttackPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(Update, update_attacks.before(move_enemies).run_if(in_state(RunState::Running)))
+            .add_systems(Update, draw_attack_telegraphs.run_if(in_state(AppState::InGame)))
+        ;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AttackKind {
+    /// Charges along a line shown during the wind-up.
+    Lunge,
+    /// Hits everything inside a circle shown during the wind-up.
+    Slam,
+}
+
+struct AttackStats {
+    /// Distance to the player at which the wind-up starts.
+    range: f32,
+    wind_up: f32,
+    active: f32,
+    recovery: f32,
+    cooldown: f32,
+    /// Effect and duration applied to the players it hits.
+    on_hit: Option<(StatusKind, f32)>,
+}
+
+impl AttackKind {
+    fn stats(self) -> AttackStats {
+        match self {
+            AttackKind::Lunge => AttackStats { range: 200., wind_up: 0.7, active: 0.3, recovery: 0.6, cooldown: 3., on_hit: None },
+            AttackKind::Slam => AttackStats { range: 90., wind_up: 0.9, active: 0.15, recovery: 0.8, cooldown: 4., on_hit: Some((StatusKind::Stun, 0.5)) },
+        }
+    }
+}
+
+const LUNGE_SPEED: f32 = 500.;
+const SLAM_RADIUS: f32 = 110.;
+
+type Attacker = (
+    Entity,
+    &'static mut Attack,
+    &'static Transform,
+    &'static mut Velocity,
+    &'static Volume,
+    &'static Knockback,
+    Option<&'static StatusEffects>,
+    Option<&'static Perception>,
+);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum AttackPhase {
+    Ready,
+    WindUp,
+    Active,
+    Recovery,
+}
+
+/// Readable melee attack: wind-up, a short window that can hit, then recovery.
+#[derive(Component, Debug)]
+pub struct Attack {
+    kind: AttackKind,
+    phase: AttackPhase,
+    timer: Timer,
+    cooldown: Timer,
+    direction: Vec2,
+    has_hit: bool,
+}
+
+impl Attack {
+    pub fn new(kind: AttackKind) -> Self {
+        Self {
+            kind,
+            phase: AttackPhase::Ready,
+            timer: Timer::default(),
+            cooldown: Timer::from_seconds(kind.stats().cooldown, TimerMode::Once),
+            direction: Vec2::ZERO,
+            has_hit: false,
+        }
+    }
+
+    pub fn for_archetype(archetype: Archetype) -> Option<Self> {
+        match archetype {
+            Archetype::Chaser => Some(Self::new(AttackKind::Lunge)),
+            Archetype::Flanker => Some(Self::new(AttackKind::Slam)),
+            Archetype::Skirmisher => None,
+        }
+    }
+
+    /// Whether the attack has taken over movement from steering.
+    pub fn is_busy(&self) -> bool {
+        self.phase != AttackPhase::Ready
+    }
+
+    fn enter(&mut self, phase: AttackPhase, duration: f32) {
+        self.phase = phase;
+        self.timer = Timer::from_seconds(duration, TimerMode::Once);
+    }
+}
+
+fn update_attacks(
+    mut attackers_query: Query<Attacker, NearEnemy>,
+    player_query: Query<(Entity, &Transform, &Volume), With<Player>>,
+    mut damage_events: EventWriter<DamageEvent>,
+    time: Res<Time>,
+) {
+    let players: Vec<_> = player_query.iter().map(|(entity, transform, _)| (entity, transform.translation.truncate())).collect();
+
+    for (entity, mut attack, transform, mut velocity, volume, knockback, status, perception) in &mut attackers_query {
+        let stats = attack.kind.stats();
+        let position = transform.translation.truncate();
+        let Some((_, player_position)) = nearest_player(&players, position) else {
+            continue;
+        };
+        let interrupted = knockback.is_stunned(time.elapsed_seconds())
+            || status.is_some_and(|status| status.speed_multiplier() == 0.);
+
+        match attack.phase {
+            AttackPhase::Ready => {
+                let unaware = perception.is_some_and(|perception| !perception.is_aware());
+                if !attack.cooldown.tick(time.delta()).finished() || interrupted || unaware {
+                    continue;
+                }
+                if position.distance(player_position) <= stats.range {
+                    attack.direction = (player_position - position).normalize_or_zero();
+                    attack.enter(AttackPhase::WindUp, stats.wind_up);
+                    velocity.0 = Vec2::ZERO;
+                }
+            }
+            AttackPhase::WindUp => {
+                // Getting hit out of the wind-up cancels the attack.
+                if interrupted {
+                    attack.enter(AttackPhase::Recovery, stats.recovery);
+                } else if attack.timer.tick(time.delta()).finished() {
+                    attack.has_hit = false;
+                    attack.enter(AttackPhase::Active, stats.active);
+                }
+            }
+            AttackPhase::Active => {
+                if attack.kind == AttackKind::Lunge {
+                    velocity.0 = attack.direction * LUNGE_SPEED;
+                }
+                // A slam catches everyone inside it, a lunge whoever it runs into.
+                let hits: Vec<_> = player_query.iter()
+                    .filter(|(_, player_transform, player_volume)| match attack.kind {
+                        AttackKind::Lunge => volume.intersects(&player_volume.0),
+                        AttackKind::Slam => position.distance(player_transform.translation.truncate()) <= SLAM_RADIUS,
+                    })
+                    .map(|(player_entity, ..)| player_entity)
+                    .collect();
+                if !hits.is_empty() && !attack.has_hit {
+                    attack.has_hit = true;
+                    for player_entity in hits {
+                        damage_events.send(DamageEvent {
+                            target: player_entity,
+                            source: Some(entity),
+                            amount: 1,
+                            kind: DamageType::Physical,
+                            on_hit: stats.on_hit,
+                            over_time: false,
+                        });
+                    }
+                }
+                if attack.timer.tick(time.delta()).finished() {
+                    attack.enter(AttackPhase::Recovery, stats.recovery);
+                    velocity.0 = Vec2::ZERO;
+                }
+            }
+            AttackPhase::Recovery => {
+                if attack.timer.tick(time.delta()).finished() {
+                    attack.phase = AttackPhase::Ready;
+                    attack.cooldown.reset();
+                }
+            }
+        }
+    }
+}
+
+fn draw_attack_telegraphs(
+    mut gizmos: Gizmos,
+    attackers_query: Query<(&Attack, &Transform), Without<Death>>,
+) {
+    for (attack, transform) in &attackers_query {
+        let position = transform.translation.truncate();
+        let (color, progress) = match attack.phase {
+            AttackPhase::WindUp => (Color::from(YELLOW).mix(&Color::from(ORANGE), attack.timer.fraction()), attack.timer.fraction()),
+            AttackPhase::Active => (Color::from(RED), 1.),
+            AttackPhase::Ready | AttackPhase::Recovery => continue,
+        };
+
+        match attack.kind {
+            AttackKind::Lunge => {
+                let reach = LUNGE_SPEED * attack.kind.stats().active;
+                gizmos.line_2d(position, position + attack.direction * reach, color.with_alpha(0.4));
+                gizmos.line_2d(position, position + attack.direction * reach * progress, color);
+            }
+            AttackKind::Slam => {
+                gizmos.circle_2d(position, SLAM_RADIUS, color);
+                gizmos.circle_2d(position, SLAM_RADIUS * progress, color);
+            }
+        }
+    }
+}
@@ -0,0 +1,277 @@
+use std::sync::Arc;
+
+use bevy::{color::palettes::css::{ORANGE, RED, WHITE, YELLOW}, prelude::*, utils::HashMap};
+
+use crate::{enemies::{Death, NearEnemy}, perception::Perception, player::{nearest_player, Player}, AppState, RunState};
+
+pub struct BehaviourPlugin;
+
+impl Plugin for BehaviourPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<BehaviourTrees>()
+            .init_resource::<BehaviourDebug>()
+            .add_event::<EnemyShootEvent>()
+            .add_systems(Update, tick_behaviours.run_if(in_state(RunState::Running)))
+            .add_systems(Update, (
+                toggle_behaviour_debug,
+                draw_active_nodes.run_if(|debug: Res<BehaviourDebug>| debug.0),
+            ).run_if(in_state(AppState::InGame)))
+        ;
+    }
+}
+
+#[derive(Event)]
+pub struct EnemyShootEvent {
+    pub origin: Vec3,
+    pub direction: Vec3,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Action {
+    Chase,
+    Flee,
+    Strafe,
+    Wait(f32),
+    Shoot,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Condition {
+    PlayerWithin(f32),
+    PlayerBeyond(f32),
+}
+
+#[derive(Debug)]
+pub enum Node {
+    Sequence(Vec<Node>),
+    Selector(Vec<Node>),
+    Condition(Condition),
+    Action(Action),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Archetype {
+    Chaser,
+    Flanker,
+    Skirmisher,
+}
+
+impl Archetype {
+    /// Fraction of every knockback impulse the archetype shrugs off.
+    pub fn knockback_resistance(self) -> f32 {
+        match self {
+            Archetype::Chaser => 0.3,
+            Archetype::Flanker => 0.5,
+            Archetype::Skirmisher => 0.,
+        }
+    }
+
+    fn tree(self) -> Node {
+        match self {
+            Archetype::Chaser => Node::Action(Action::Chase),
+            Archetype::Flanker => Node::Selector(vec![
+                Node::Sequence(vec![
+                    Node::Condition(Condition::PlayerBeyond(250.)),
+                    Node::Action(Action::Chase),
+                ]),
+                Node::Action(Action::Strafe),
+            ]),
+            Archetype::Skirmisher => Node::Selector(vec![
+                Node::Sequence(vec![
+                    Node::Condition(Condition::PlayerWithin(150.)),
+                    Node::Action(Action::Flee),
+                ]),
+                Node::Sequence(vec![
+                    Node::Condition(Condition::PlayerWithin(350.)),
+                    Node::Action(Action::Wait(1.2)),
+                    Node::Action(Action::Shoot),
+                ]),
+                Node::Action(Action::Chase),
+            ]),
+        }
+    }
+}
+
+/// One shared tree per archetype, cloned into each enemy's [`Behaviour`] by reference.
+#[derive(Resource)]
+pub struct BehaviourTrees(HashMap<Archetype, Arc<Node>>);
+
+impl Default for BehaviourTrees {
+    fn default() -> Self {
+        Self(
+            [Archetype::Chaser, Archetype::Flanker, Archetype::Skirmisher]
+                .into_iter()
+                .map(|archetype| (archetype, Arc::new(archetype.tree())))
+                .collect()
+        )
+    }
+}
+
+impl BehaviourTrees {
+    pub fn behaviour(&self, archetype: Archetype) -> Behaviour {
+        Behaviour {
+            archetype,
+            tree: self.0[&archetype].clone(),
+            action: Action::Chase,
+            active: Vec::new(),
+            waiting: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Status {
+    Success,
+    Failure,
+    Running,
+}
+
+struct Context {
+    player_distance: f32,
+    now: f32,
+}
+
+#[derive(Component, Debug)]
+pub struct Behaviour {
+    pub archetype: Archetype,
+    tree: Arc<Node>,
+    /// Movement action of the node that is currently running.
+    pub action: Action,
+    /// Child indices from the root to the running node.
+    pub active: Vec<usize>,
+    waiting: Option<(Vec<usize>, f32)>,
+}
+
+impl Behaviour {
+    pub fn active_node(&self) -> &Node {
+        self.active.iter().fold(&*self.tree, |node, &index| match node {
+            Node::Sequence(children) | Node::Selector(children) => &children[index],
+            _ => node,
+        })
+    }
+
+    fn tick(&mut self, context: &Context) -> bool {
+        let tree = self.tree.clone();
+        let mut path = Vec::new();
+        let mut fire = false;
+        self.active.clear();
+        self.tick_node(&tree, &mut path, context, &mut fire);
+        if self.waiting.as_ref().is_some_and(|(waiting, _)| *waiting != self.active) {
+            self.waiting = None;
+        }
+        fire
+    }
+
+    fn tick_node(&mut self, node: &Node, path: &mut Vec<usize>, context: &Context, fire: &mut bool) -> Status {
+        match node {
+            Node::Sequence(children) | Node::Selector(children) => {
+                let stop_on = if matches!(node, Node::Sequence(_)) { Status::Failure } else { Status::Success };
+                for (index, child) in children.iter().enumerate() {
+                    path.push(index);
+                    let status = self.tick_node(child, path, context, fire);
+                    path.pop();
+                    if status == Status::Running || status == stop_on {
+                        return status;
+                    }
+                }
+                if stop_on == Status::Failure { Status::Success } else { Status::Failure }
+            }
+            Node::Condition(condition) => {
+                let passed = match *condition {
+                    Condition::PlayerWithin(distance) => context.player_distance <= distance,
+                    Condition::PlayerBeyond(distance) => context.player_distance > distance,
+                };
+                if passed { Status::Success } else { Status::Failure }
+            }
+            Node::Action(Action::Wait(duration)) => {
+                let until = match &self.waiting {
+                    Some((waiting, until)) if waiting == path => *until,
+                    _ => context.now + duration,
+                };
+                if context.now >= until {
+                    self.waiting = None;
+                    return Status::Success;
+                }
+                self.waiting = Some((path.clone(), until));
+                self.running(Action::Wait(*duration), path)
+            }
+            Node::Action(Action::Shoot) => {
+                *fire = true;
+                Status::Success
+            }
+            Node::Action(action) => self.running(*action, path),
+        }
+    }
+
+    fn running(&mut self, action: Action, path: &[usize]) -> Status {
+        self.action = action;
+        self.active = path.to_vec();
+        Status::Running
+    }
+}
+
+/// Toggles drawing of each enemy's active action, for debugging trees.
+#[derive(Resource, Default)]
+pub struct BehaviourDebug(pub bool);
+
+pub fn tick_behaviours(
+    mut enemies_query: Query<(&Transform, &mut Behaviour, Option<&Perception>), NearEnemy>,
+    player_query: Query<(Entity, &Transform), With<Player>>,
+    mut shoot_events: EventWriter<EnemyShootEvent>,
+    time: Res<Time>,
+) {
+    let players: Vec<_> = player_query.iter().map(|(entity, transform)| (entity, transform.translation.truncate())).collect();
+
+    for (transform, mut behaviour, perception) in &mut enemies_query {
+        if perception.is_some_and(|perception| !perception.is_aware()) {
+            continue;
+        }
+        let Some((_, player_position)) = nearest_player(&players, transform.translation.truncate()) else {
+            continue;
+        };
+        let offset = player_position.extend(transform.translation.z) - transform.translation;
+        let context = Context {
+            player_distance: offset.length(),
+            now: time.elapsed_seconds(),
+        };
+        if behaviour.tick(&context) {
+            shoot_events.send(EnemyShootEvent {
+                origin: transform.translation,
+                direction: offset.normalize_or_zero(),
+            });
+        }
+    }
+}
+
+fn toggle_behaviour_debug(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    mut debug: ResMut<BehaviourDebug>,
+    enemies_query: Query<(Entity, &Behaviour), Without<Death>>,
+) {
+    if !keyboard_input.just_pressed(KeyCode::F3) {
+        return;
+    }
+
+    debug.0 = !debug.0;
+    if debug.0 {
+        for (entity, behaviour) in &enemies_query {
+            info!("{entity}: {:?} running {:?}", behaviour.archetype, behaviour.active_node());
+        }
+    }
+}
+
+fn draw_active_nodes(
+    mut gizmos: Gizmos,
+    enemies_query: Query<(&Transform, &Behaviour), Without<Death>>,
+) {
+    for (transform, behaviour) in &enemies_query {
+        let color = match behaviour.action {
+            Action::Chase => Color::from(RED),
+            Action::Flee => Color::from(YELLOW),
+            Action::Strafe => Color::from(ORANGE),
+            Action::Wait(_) | Action::Shoot => Color::from(WHITE),
+        };
+        gizmos.circle_2d(transform.translation.xy(), 28., color);
+    }
+}
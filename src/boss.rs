@@ -0,0 +1,235 @@
+use std::f32::consts::TAU;
+
+use bevy::{color::palettes::css::{CRIMSON, MAROON, ORANGE_RED}, prelude::*};
+
+use crate::{behaviour::EnemyShootEvent, bounding::{Intersects, Shape}, damage::{Bounty, Resistances}, enemies::{Death, Enemy, Health}, kinematics::{integrate_motion, Velocity}, loot::{DropTable, BOSS_DROPS}, player::{nearest_player, Player}, status::StatusEffects, AppState, RunState};
+
+pub const BOSS_HEALTH: i32 = 150;
+const BOSS_BOUNTY: i32 = 50;
+const BOSS_RADIUS: f32 = 60.;
+const BOSS_SPAWN_DISTANCE: f32 = 600.;
+/// Seconds into the run at which each boss arrives.
+const BOSS_SCHEDULE: [f32; 3] = [90., 240., 420.];
+
+type LiveBoss = (Without<Death>, Without<Player>);
+
+pub struct BossPlugin;
+
+impl Plugin for BossPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<BossSchedule>()
+            .add_systems(OnEnter(AppState::InGame), reset_boss_schedule)
+            .add_systems(Update, (
+                spawn_boss,
+                update_boss_phase,
+                (move_boss.before(integrate_motion), boss_attack),
+            ).chain().run_if(in_state(RunState::Running)))
+        ;
+    }
+}
+
+#[derive(Clone, Copy)]
+enum Movement {
+    Chase,
+    Orbit(f32),
+}
+
+#[derive(Clone, Copy)]
+enum Pattern {
+    /// Fan of bullets aimed at the player.
+    Aimed { shots: u32, spread: f32 },
+    /// Evenly spaced ring around the boss.
+    Ring { shots: u32 },
+    /// Rotating arms that sweep a little further every volley.
+    Spiral { arms: u32, step: f32 },
+}
+
+struct BossPhase {
+    /// Fraction of max health at or below which the phase starts.
+    threshold: f32,
+    speed: f32,
+    movement: Movement,
+    pattern: Pattern,
+    interval: f32,
+    color: Srgba,
+}
+
+const PHASES: [BossPhase; 3] = [
+    BossPhase {
+        threshold: 1.,
+        speed: 40.,
+        movement: Movement::Chase,
+        pattern: Pattern::Aimed { shots: 3, spread: 0.3 },
+        interval: 1.5,
+        color: MAROON,
+    },
+    BossPhase {
+        threshold: 0.6,
+        speed: 80.,
+        movement: Movement::Orbit(300.),
+        pattern: Pattern::Ring { shots: 12 },
+        interval: 2.,
+        color: CRIMSON,
+    },
+    BossPhase {
+        threshold: 0.3,
+        speed: 110.,
+        movement: Movement::Chase,
+        pattern: Pattern::Spiral { arms: 4, step: 0.25 },
+        interval: 0.3,
+        color: ORANGE_RED,
+    },
+];
+
+#[derive(Component)]
+pub struct Boss {
+    phase: usize,
+    fire_timer: Timer,
+    volley: u32,
+}
+
+#[derive(Resource, Default)]
+pub struct BossSchedule {
+    elapsed: f32,
+    next: usize,
+}
+
+fn reset_boss_schedule(mut schedule: ResMut<BossSchedule>) {
+    *schedule = BossSchedule::default();
+}
+
+fn spawn_boss(
+    mut commands: Commands,
+    mut schedule: ResMut<BossSchedule>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    player_query: Query<&Transform, With<Player>>,
+    time: Res<Time>,
+) {
+    schedule.elapsed += time.delta_seconds();
+    let Some(&spawn_time) = BOSS_SCHEDULE.get(schedule.next) else {
+        return;
+    };
+    if schedule.elapsed < spawn_time {
+        return;
+    }
+    schedule.next += 1;
+
+    let players: Vec<_> = player_query.iter().map(|transform| transform.translation).collect();
+    if players.is_empty() {
+        return;
+    }
+
+    // Arrives above the middle of the group.
+    let phase = &PHASES[0];
+    let position = players.iter().sum::<Vec3>() / players.len() as f32 + Vec3::Y * BOSS_SPAWN_DISTANCE;
+    commands.spawn((
+        ColorMesh2dBundle {
+            mesh: meshes.add(RegularPolygon::new(BOSS_RADIUS, 6)).into(),
+            material: materials.add(Color::from(phase.color)),
+            transform: Transform::from_translation(position),
+            ..default()
+        },
+        Enemy,
+        Boss {
+            phase: 0,
+            fire_timer: Timer::from_seconds(phase.interval, TimerMode::Repeating),
+            volley: 0,
+        },
+        Health::new(BOSS_HEALTH),
+        Velocity::default(),
+        Bounty(BOSS_BOUNTY),
+        Resistances { fire: 0.5, poison: 0.5, ..default() },
+        DropTable(&BOSS_DROPS),
+        Shape::Ellipse(Ellipse::new(BOSS_RADIUS, BOSS_RADIUS)),
+        Intersects::default(),
+    ));
+}
+
+fn update_boss_phase(
+    mut boss_query: Query<(&mut Boss, &Health, &Handle<ColorMaterial>), Without<Death>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    for (mut boss, health, material_handle) in &mut boss_query {
+        let fraction = health.fraction();
+        let phase = PHASES.iter().rposition(|phase| fraction <= phase.threshold).unwrap_or(0);
+        if phase == boss.phase {
+            continue;
+        }
+
+        boss.phase = phase;
+        boss.fire_timer = Timer::from_seconds(PHASES[phase].interval, TimerMode::Repeating);
+        if let Some(material) = materials.get_mut(material_handle) {
+            material.color = Color::from(PHASES[phase].color);
+        }
+    }
+}
+
+fn move_boss(
+    mut boss_query: Query<(&Boss, &mut Transform, &mut Velocity, Option<&StatusEffects>), LiveBoss>,
+    player_query: Query<(Entity, &Transform), With<Player>>,
+    time: Res<Time>,
+) {
+    let players: Vec<_> = player_query.iter().map(|(entity, transform)| (entity, transform.translation.truncate())).collect();
+
+    for (boss, mut transform, mut velocity, status) in &mut boss_query {
+        let phase = &PHASES[boss.phase];
+        let Some((_, player_position)) = nearest_player(&players, transform.translation.truncate()) else {
+            velocity.0 = Vec2::ZERO;
+            continue;
+        };
+        let to_player = player_position - transform.translation.truncate();
+        let direction = match phase.movement {
+            Movement::Chase => to_player.normalize_or_zero(),
+            Movement::Orbit(radius) => {
+                let inward = to_player.normalize_or_zero() * (to_player.length() - radius) / radius;
+                (to_player.perp().normalize_or_zero() + inward).normalize_or_zero()
+            }
+        };
+        velocity.0 = direction * phase.speed * status.map_or(1., StatusEffects::speed_multiplier);
+        transform.rotate_z(time.delta_seconds());
+    }
+}
+
+fn boss_attack(
+    mut boss_query: Query<(&mut Boss, &Transform), Without<Death>>,
+    player_query: Query<(Entity, &Transform), With<Player>>,
+    mut shoot_events: EventWriter<EnemyShootEvent>,
+    time: Res<Time>,
+) {
+    let players: Vec<_> = player_query.iter().map(|(entity, transform)| (entity, transform.translation.truncate())).collect();
+
+    for (mut boss, transform) in &mut boss_query {
+        if !boss.fire_timer.tick(time.delta()).just_finished() {
+            continue;
+        }
+        let Some((_, player_position)) = nearest_player(&players, transform.translation.truncate()) else {
+            continue;
+        };
+        boss.volley += 1;
+
+        let aim = (player_position - transform.translation.truncate()).normalize_or_zero();
+        let directions: Vec<Vec2> = match PHASES[boss.phase].pattern {
+            Pattern::Aimed { shots, spread } => (0..shots)
+                .map(|shot| {
+                    let offset = (shot as f32 - (shots - 1) as f32 / 2.) * spread;
+                    Vec2::from_angle(offset).rotate(aim)
+                })
+                .collect(),
+            Pattern::Ring { shots } => (0..shots)
+                .map(|shot| Vec2::from_angle(shot as f32 * TAU / shots as f32))
+                .collect(),
+            Pattern::Spiral { arms, step } => (0..arms)
+                .map(|arm| Vec2::from_angle(arm as f32 * TAU / arms as f32 + boss.volley as f32 * step))
+                .collect(),
+        };
+
+        for direction in directions {
+            shoot_events.send(EnemyShootEvent {
+                origin: transform.translation,
+                direction: direction.extend(0.),
+            });
+        }
+    }
+}
@@ -1,13 +1,13 @@
 use bevy::{math::bounding::*, prelude::*, color::palettes::basic::*};
 
-use crate::{enemies::Death, AppState};
+use crate::{enemies::Death, RunState};
 
 pub struct BoundingPlugin;
 
 impl Plugin for BoundingPlugin {
     fn build(&self, app: &mut App) {
         app
-            .add_systems(PreUpdate, update_volumes.run_if(in_state(AppState::InGame)))
+            .add_systems(PreUpdate, update_volumes.run_if(in_state(RunState::Running)))
             // .add_systems(PostUpdate, (
             //     //render_shapes,
             //     //,render_volumes
@@ -1,70 +1,253 @@
-use bevy::prelude::*;
+use std::{f32::consts::TAU, time::Duration};
+
+use bevy::{color::palettes::css::ORANGE, prelude::*};
 use rand::{random, Rng};
 
-use crate::{bounding::{Intersects, Shape}, player::{Player, PlayerMoveEvent}, AppState};
+use crate::{behaviour::EnemyShootEvent, bounding::{Intersects, Shape}, input::{DeviceInputs, InputAction, InputDevice}, kinematics::Velocity, player::{Player, PlayerMoveEvent}, stats::{Stat, Stats}, status::StatusKind, AppState, RunState};
 
-const BULLET_SPEED: f32 = 300.;
 const BULLET_LIFETIME: f32 = 2.;
+const HOSTILE_BULLET_SPEED: f32 = 180.;
+const HOSTILE_BULLET_STATUS: (StatusKind, f32) = (StatusKind::Slow, 1.5);
+/// Spread between the extra bullets of a multishot, in radians.
+const MULTISHOT_SPREAD: f32 = 0.15;
+const NOVA_INTERVAL: f32 = 3.;
+const NOVA_BULLETS: u32 = 6;
+const NOVA_BULLETS_PER_LEVEL: u32 = 3;
+pub const NOVA_MAX_LEVEL: u32 = 3;
+const SUPERNOVA_INTERVAL: f32 = 1.5;
+const SUPERNOVA_BULLETS: u32 = 24;
+const SUPERNOVA_STATUS: (StatusKind, f32) = (StatusKind::Burn, 3.);
 
 pub struct BulletPlugin;
 
 impl Plugin for BulletPlugin {
     fn build(&self, app: &mut App) {
         app
-        .add_systems(Update, (spawn_bullets, move_bullets).run_if(in_state(AppState::InGame)))
-        .add_systems(PostUpdate, despawn_bullet.run_if(in_state(AppState::InGame)))
+        .add_systems(Update, (spawn_bullets, fire_nova, spawn_enemy_bullets).run_if(in_state(RunState::Running)))
+        .add_systems(PostUpdate, despawn_bullet.run_if(in_state(RunState::Running)))
+        .add_systems(OnExit(AppState::InGame), despawn_bullets)
         ;
     }
 }
 
 #[derive(Component)]
 pub struct Bullet {
-    direction: Vec3,
+    pub direction: Vec3,
+    pub damage: i32,
+    /// Strength of the push applied to whatever the bullet hits.
+    pub knockback: f32,
+    pub status: Option<(StatusKind, f32)>,
+    /// Entity credited with the damage, if it is still known.
+    pub source: Option<Entity>,
     spawn_time: f32
 }
 
+/// Tuning shared by every bullet a weapon fires.
+#[derive(Debug, Clone, Copy)]
+pub struct Weapon {
+    /// Impulse a bullet gives the enemy it hits.
+    pub knockback: f32,
+    /// Effect and duration a bullet applies on hit when it procs.
+    pub status: Option<(StatusKind, f32)>,
+    /// Chance for each bullet to carry `status`.
+    pub proc_chance: f64,
+}
+
+const MAIN_GUN: Weapon = Weapon { knockback: 250., status: Some((StatusKind::Poison, 5.)), proc_chance: 0.25 };
+const NOVA: Weapon = Weapon { knockback: 120., status: Some((StatusKind::Freeze, 1.)), proc_chance: 0.2 };
+
+impl Weapon {
+    /// Rolls the proc chance for one bullet.
+    fn roll_status(&self, rng: &mut impl Rng) -> Option<(StatusKind, f32)> {
+        self.status.filter(|_| rng.gen_bool(self.proc_chance.clamp(0., 1.)))
+    }
+}
+
+/// Weapons and main gun upgrades the player picked up during the run.
+#[derive(Component, Debug)]
+pub struct Arsenal {
+    pub gun: Weapon,
+    pub nova: Weapon,
+    /// Extra bullets the main gun fires with every shot.
+    pub multishot: u32,
+    nova_level: u32,
+    supernova: bool,
+    nova_timer: Timer,
+    /// Direction of the last shot, kept firing while Fire is held.
+    facing: Vec3,
+    next_shot: f32,
+}
+
+impl Default for Arsenal {
+    fn default() -> Self {
+        Self {
+            gun: MAIN_GUN,
+            nova: NOVA,
+            multishot: 0,
+            nova_level: 0,
+            supernova: false,
+            nova_timer: Timer::from_seconds(NOVA_INTERVAL, TimerMode::Repeating),
+            facing: Vec3::ZERO,
+            next_shot: 0.,
+        }
+    }
+}
+
+impl Arsenal {
+    /// Level of the nova ring, 0 until it has been picked.
+    pub fn nova_level(&self) -> u32 {
+        self.nova_level
+    }
+
+    pub fn has_supernova(&self) -> bool {
+        self.supernova
+    }
+
+    pub fn level_nova(&mut self) {
+        self.nova_level = (self.nova_level + 1).min(NOVA_MAX_LEVEL);
+    }
+
+    /// Turns a maxed nova into a faster, denser ring that sets enemies on fire.
+    pub fn evolve_nova(&mut self) {
+        self.supernova = true;
+        self.nova.status = Some(SUPERNOVA_STATUS);
+        self.nova.proc_chance = 1.;
+        self.nova_timer.set_duration(Duration::from_secs_f32(SUPERNOVA_INTERVAL));
+    }
+}
+
+/// Marks bullets fired by enemies, which hurt the player instead of enemies.
+#[derive(Component)]
+pub struct Hostile;
+
 pub fn spawn_bullets(
     mut commands: Commands,
     mut player_move_events: EventReader<PlayerMoveEvent>,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
-    player_query: Query<&Transform, With<Player>>,
+    mut player_query: Query<(Entity, &Transform, &Stats, &mut Arsenal, &InputDevice), With<Player>>,
+    inputs: Res<DeviceInputs>,
     time: Res<Time>
 ) {
-    let Ok(player_transform) = player_query.get_single() else {
-        return;
-    };
+    let moves: Vec<_> = player_move_events.read().map(|move_event| (move_event.player, move_event.direction)).collect();
+    let mut rng = rand::thread_rng();
 
-    let Some(move_event) = player_move_events.read().next() else {
-        return;
-    };
+    for (player_entity, player_transform, stats, mut arsenal, device) in &mut player_query {
+        let input = inputs.get(*device);
 
-    let mut color = Color::srgb(random(), random(), random());
-    let mut rng = rand::thread_rng();
-    let direction = move_event.0.clone() + Vec3::new(
-        rng.gen_range(-0.4..0.4),
-        rng.gen_range(-0.4..0.4),
-        0.
-    );
-
-    let ellipse_primitive = Ellipse::new(3.0, 6.0);
-    commands.spawn((ColorMesh2dBundle {
-        mesh: meshes.add(ellipse_primitive).into(),
-        material: materials.add(color),
-        transform: Transform::from_translation(player_transform.translation),
-        ..default()
-    }, Bullet {
-        direction: direction.normalize(),
-        spawn_time: time.elapsed_seconds()
-    }, Shape::Ellipse(ellipse_primitive),  Intersects::default()));
+        // Moving fires the way the player moves. Holding Fire shoots toward the right stick
+        // when it is tilted, otherwise the way the player last moved.
+        let moved = moves.iter().find(|(player, _)| *player == player_entity).map(|&(_, direction)| direction);
+        let firing = input.actions.pressed(InputAction::Fire) && time.delta_seconds() > 0.;
+        let aim = match moved {
+            _ if firing && input.analog.aim != Vec2::ZERO => input.analog.aim.extend(0.),
+            Some(direction) => direction,
+            None if firing && arsenal.facing != Vec3::ZERO => arsenal.facing,
+            None => continue,
+        };
+        arsenal.facing = aim;
+
+        if time.elapsed_seconds() < arsenal.next_shot {
+            continue;
+        }
+        arsenal.next_shot = time.elapsed_seconds() + 1. / stats.get(Stat::FireRate).max(0.1);
+
+        let color = Color::srgb(random(), random(), random());
+        let ellipse_primitive = Ellipse::new(3.0, 6.0);
+        let mesh = meshes.add(ellipse_primitive);
+        let material = materials.add(color);
+
+        for shot in 0..=arsenal.multishot {
+            // Extra bullets fan out on alternating sides of the aim.
+            let side = if shot % 2 == 0 { 1. } else { -1. };
+            let angle = side * shot.div_ceil(2) as f32 * MULTISHOT_SPREAD;
+            let direction = (Quat::from_rotation_z(angle) * aim + Vec3::new(
+                rng.gen_range(-0.4..0.4),
+                rng.gen_range(-0.4..0.4),
+                0.
+            )).normalize();
+
+            commands.spawn((ColorMesh2dBundle {
+                mesh: mesh.clone().into(),
+                material: material.clone(),
+                transform: Transform::from_translation(player_transform.translation),
+                ..default()
+            }, Bullet {
+                direction,
+                damage: stats.get(Stat::Damage).round() as i32,
+                knockback: arsenal.gun.knockback,
+                status: arsenal.gun.roll_status(&mut rng),
+                source: Some(player_entity),
+                spawn_time: time.elapsed_seconds()
+            }, Velocity(direction.truncate() * stats.get(Stat::ProjectileSpeed)), Shape::Ellipse(ellipse_primitive),  Intersects::default()));
+        }
+    }
 }
 
-pub fn move_bullets(
-    mut bullet_query: Query<(&mut Transform, &Bullet)>,
+/// Fires a ring of bullets around the player once the nova has been picked.
+fn fire_nova(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut player_query: Query<(Entity, &Transform, &Stats, &mut Arsenal), With<Player>>,
     time: Res<Time>
 ) {
-    for (mut transform, bullet) in &mut bullet_query {
-        transform.translation += bullet.direction * time.delta_seconds() * BULLET_SPEED;
+    for (player_entity, player_transform, stats, mut arsenal) in &mut player_query {
+        if arsenal.nova_level == 0 || !arsenal.nova_timer.tick(time.delta()).just_finished() {
+            continue;
+        }
+
+        let count = if arsenal.supernova {
+            SUPERNOVA_BULLETS
+        } else {
+            NOVA_BULLETS + NOVA_BULLETS_PER_LEVEL * (arsenal.nova_level - 1)
+        };
+        let mut rng = rand::thread_rng();
+        let circle_primitive = Circle::new(4.0);
+        let mesh = meshes.add(circle_primitive);
+        let material = materials.add(Color::from(ORANGE));
+
+        for index in 0..count {
+            let direction = Vec2::from_angle(index as f32 * TAU / count as f32);
+            commands.spawn((ColorMesh2dBundle {
+                mesh: mesh.clone().into(),
+                material: material.clone(),
+                transform: Transform::from_translation(player_transform.translation),
+                ..default()
+            }, Bullet {
+                direction: direction.extend(0.),
+                damage: stats.get(Stat::Damage).round() as i32,
+                knockback: arsenal.nova.knockback,
+                status: arsenal.nova.roll_status(&mut rng),
+                source: Some(player_entity),
+                spawn_time: time.elapsed_seconds()
+            }, Velocity(direction * stats.get(Stat::ProjectileSpeed)), Shape::Ellipse(Ellipse::new(4.0, 4.0)), Intersects::default()));
+        }
+    }
+}
+
+pub fn spawn_enemy_bullets(
+    mut commands: Commands,
+    mut enemy_shoot_events: EventReader<EnemyShootEvent>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    time: Res<Time>
+) {
+    for shoot_event in enemy_shoot_events.read() {
+        let circle_primitive = Circle::new(5.0);
+        commands.spawn((ColorMesh2dBundle {
+            mesh: meshes.add(circle_primitive).into(),
+            material: materials.add(Color::srgb(1., 0.3, 0.1)),
+            transform: Transform::from_translation(shoot_event.origin),
+            ..default()
+        }, Bullet {
+            direction: shoot_event.direction,
+            damage: 1,
+            knockback: 0.,
+            status: Some(HOSTILE_BULLET_STATUS),
+            source: None,
+            spawn_time: time.elapsed_seconds()
+        }, Hostile, Velocity(shoot_event.direction.truncate() * HOSTILE_BULLET_SPEED), Shape::Ellipse(Ellipse::new(5.0, 5.0)), Intersects::default()));
     }
 }
 
@@ -78,4 +261,14 @@ pub fn despawn_bullet(
             commands.entity(entity).despawn();
         }
     }
-}
\ No newline at end of file
+}
+
+/// Clears player and enemy bullets still flying when the run ends.
+fn despawn_bullets(
+    mut commands: Commands,
+    bullet_query: Query<Entity, With<Bullet>>,
+) {
+    for entity in &bullet_query {
+        commands.entity(entity).despawn();
+    }
+}
@@ -3,6 +3,10 @@ use bevy::prelude::*;
 use crate::player::Player;
 
 const DIR_LERP: f32 = 0.02;
+/// Room kept between the outermost players and the edge of the view.
+const FRAME_MARGIN: f32 = 200.;
+const MAX_ZOOM: f32 = 2.5;
+const ZOOM_LERP: f32 = 0.05;
 
 pub struct CameraPlugin;
 
@@ -22,23 +26,34 @@ pub fn setup_camera(
     commands.spawn(Camera2dBundle::default());
 }
 
+/// Follows the middle of all players, zooming out as far as needed to keep everyone in view.
 pub fn move_camera(
     player_query: Query<&Transform, (With<Player>, Without<Camera2d>)>,
-    mut camera_query: Query<&mut Transform, With<Camera2d>>
+    mut camera_query: Query<(&mut Transform, &mut OrthographicProjection), With<Camera2d>>
 ) {
-    let Ok(player_transform) = player_query.get_single() else {
+    let mut players = player_query.iter().map(|transform| transform.translation.truncate());
+    let Some(first) = players.next() else {
         return;
     };
+    let (min, max) = players.fold((first, first), |(min, max), position| (min.min(position), max.max(position)));
 
-    let Ok(mut camera_transform) = camera_query.get_single_mut() else {
+    let Ok((mut camera_transform, mut projection)) = camera_query.get_single_mut() else {
         return;
     };
 
+    let center = (min + max) / 2.;
     let direction = Vec3::new(
-        player_transform.translation.x,
-        player_transform.translation.y,
+        center.x,
+        center.y,
         camera_transform.translation.z
     );
 
     camera_transform.translation = camera_transform.translation.lerp(direction, DIR_LERP);
-}
\ No newline at end of file
+
+    // Size of the view at the default zoom.
+    let view = projection.area.size() / projection.scale;
+    if view.x > 0. && view.y > 0. {
+        let needed = ((max - min + Vec2::splat(2. * FRAME_MARGIN)) / view).max_element().clamp(1., MAX_ZOOM);
+        projection.scale += (needed - projection.scale) * ZOOM_LERP;
+    }
+}
@@ -0,0 +1,172 @@
+use bevy::{color::palettes::css::{DARK_ORANGE, PURPLE, SEA_GREEN}, prelude::*, utils::HashMap};
+
+use crate::{bounding::Shape, bullet::Arsenal, dash::Dash, enemies::{EnemyKilledEvent, Health}, input::InputDevice, player::{Player, PLAYER_SIZE}, stats::{Stat, Stats}, RunState};
+
+const CRIT_CHANCE: f64 = 0.1;
+const MARKSMAN_CRIT_CHANCE: f64 = 0.25;
+const FLEET_DASH_CHARGES: u32 = 2;
+/// Kills it takes for the bloodthirst passive to restore one health.
+const BLOODTHIRST_KILLS: u32 = 15;
+
+pub struct CharacterPlugin;
+
+impl Plugin for CharacterPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<Roster>()
+            .add_systems(Update, bloodthirst.run_if(in_state(RunState::Running)))
+        ;
+    }
+}
+
+pub const CHARACTERS: [Character; 3] = [Character::Gunner, Character::Bulwark, Character::Scout];
+
+/// Playable character, picked in the menu and attached to the player it spawned.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Character {
+    #[default]
+    Gunner,
+    Bulwark,
+    Scout,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Passive {
+    /// Higher critical hit chance.
+    Marksman,
+    /// Kills slowly heal.
+    Bloodthirst,
+    /// Starts with an extra dash charge.
+    Fleet,
+}
+
+/// A player who joined in the menu: the device they play with and the character they picked.
+#[derive(Debug, Clone, Copy)]
+pub struct Pick {
+    pub device: InputDevice,
+    pub character: Character,
+}
+
+pub const MAX_PLAYERS: usize = 4;
+
+/// Players the next run starts with, in join order.
+#[derive(Resource, Debug, Default)]
+pub struct Roster(pub Vec<Pick>);
+
+impl Roster {
+    pub fn contains(&self, device: InputDevice) -> bool {
+        self.0.iter().any(|pick| pick.device == device)
+    }
+}
+
+impl Character {
+    pub fn name(self) -> &'static str {
+        match self {
+            Character::Gunner => "Gunner",
+            Character::Bulwark => "Bulwark",
+            Character::Scout => "Scout",
+        }
+    }
+
+    /// Stats, starting weapon and passive, one per line.
+    pub fn description(self) -> &'static str {
+        match self {
+            Character::Gunner => "Balanced stats\nSplit Shot\nMarksman: 25% crit chance",
+            Character::Bulwark => "Slow, tough, hits hard\nNova\nBloodthirst: heal every 15 kills",
+            Character::Scout => "Fast and fragile\nRapid fire\nFleet: two dash charges",
+        }
+    }
+
+    pub fn color(self) -> Color {
+        Color::from(match self {
+            Character::Gunner => PURPLE,
+            Character::Bulwark => DARK_ORANGE,
+            Character::Scout => SEA_GREEN,
+        })
+    }
+
+    /// Unit sized mesh, the player scales it up to `PLAYER_SIZE`.
+    pub fn mesh(self) -> Mesh {
+        match self {
+            Character::Gunner => Rectangle::default().into(),
+            Character::Bulwark => Circle::new(0.5).into(),
+            Character::Scout => Triangle2d::new(Vec2::new(0., 0.5), Vec2::new(-0.5, -0.5), Vec2::new(0.5, -0.5)).into(),
+        }
+    }
+
+    pub fn shape(self) -> Shape {
+        let half = PLAYER_SIZE / 2.;
+        match self {
+            Character::Gunner => Shape::Rectangle(Rectangle::from_length(PLAYER_SIZE)),
+            Character::Bulwark => Shape::Ellipse(Ellipse::new(half, half)),
+            Character::Scout => Shape::Triangle(Triangle2d::new(Vec2::new(0., half), Vec2::new(-half, -half), Vec2::new(half, -half))),
+        }
+    }
+
+    pub fn stats(self) -> Stats {
+        match self {
+            Character::Gunner => Stats::default(),
+            Character::Bulwark => Stats::default()
+                .with_base(Stat::MoveSpeed, 160.)
+                .with_base(Stat::MaxHealth, 5.)
+                .with_base(Stat::FireRate, 12.)
+                .with_base(Stat::Damage, 2.),
+            Character::Scout => Stats::default()
+                .with_base(Stat::MoveSpeed, 260.)
+                .with_base(Stat::MaxHealth, 2.)
+                .with_base(Stat::FireRate, 28.),
+        }
+    }
+
+    /// Weapons the character starts the run with.
+    pub fn arsenal(self) -> Arsenal {
+        let mut arsenal = Arsenal::default();
+        match self {
+            Character::Gunner => arsenal.multishot = 1,
+            Character::Bulwark => arsenal.level_nova(),
+            Character::Scout => {}
+        }
+        arsenal
+    }
+
+    pub fn passive(self) -> Passive {
+        match self {
+            Character::Gunner => Passive::Marksman,
+            Character::Bulwark => Passive::Bloodthirst,
+            Character::Scout => Passive::Fleet,
+        }
+    }
+
+    pub fn crit_chance(self) -> f64 {
+        if self.passive() == Passive::Marksman { MARKSMAN_CRIT_CHANCE } else { CRIT_CHANCE }
+    }
+
+    pub fn dash(self) -> Dash {
+        if self.passive() == Passive::Fleet { Dash::with_charges(FLEET_DASH_CHARGES) } else { Dash::default() }
+    }
+}
+
+fn bloodthirst(
+    mut kill_events: EventReader<EnemyKilledEvent>,
+    mut player_query: Query<(&Character, &mut Health), With<Player>>,
+    mut kills: Local<HashMap<Entity, u32>>,
+) {
+    for event in kill_events.read() {
+        let Some(killer) = event.killer else {
+            continue;
+        };
+        let Ok((character, mut health)) = player_query.get_mut(killer) else {
+            continue;
+        };
+        if character.passive() != Passive::Bloodthirst {
+            continue;
+        }
+
+        let count = kills.entry(killer).or_default();
+        *count += 1;
+        if *count >= BLOODTHIRST_KILLS {
+            *count = 0;
+            health.heal(1);
+        }
+    }
+}
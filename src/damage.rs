@@ -0,0 +1,306 @@
+use bevy::{ecs::system::SystemParam, prelude::*};
+use rand::Rng;
+
+use crate::{elite::Elite, enemies::{enemy_bullet_collision, enemy_player_collision, hostile_bullet_player_collision, play_death, Death, Enemy, EnemyKilledEvent, Health}, status::{ApplyStatusEvent, StatusKind}, RunState};
+
+const BLINK_RATE: f32 = 10.;
+
+type Target = (
+    &'static mut Health,
+    Option<&'static Resistances>,
+    Option<&'static IFrames>,
+    Option<&'static Invulnerable>,
+    Option<&'static mut Elite>,
+    Option<&'static Bounty>,
+    Option<&'static LastHit>,
+    Has<Enemy>,
+);
+
+pub struct DamagePlugin;
+
+impl Plugin for DamagePlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_event::<DamageEvent>()
+            .add_systems(Update, (
+                process_damage
+                    .after(enemy_bullet_collision)
+                    .after(enemy_player_collision)
+                    .after(hostile_bullet_player_collision)
+                    .before(play_death),
+                blink_invulnerable,
+            ).run_if(in_state(RunState::Running)))
+        ;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DamageType {
+    Physical,
+    Fire,
+    Poison,
+    Explosion,
+}
+
+/// Every change to `Health` that hurts goes through one of these.
+#[derive(Event, Debug, Clone, Copy)]
+pub struct DamageEvent {
+    pub target: Entity,
+    /// Entity credited with the hit, used for critical hits and lifesteal.
+    pub source: Option<Entity>,
+    pub amount: i32,
+    pub kind: DamageType,
+    /// Status effect applied if the hit lands.
+    pub on_hit: Option<(StatusKind, f32)>,
+    /// Damage over time from a status effect, which goes around elite shields.
+    pub over_time: bool,
+}
+
+/// Multipliers on incoming damage per type, below 1 to resist and above to be weak.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Resistances {
+    pub physical: f32,
+    pub fire: f32,
+    pub poison: f32,
+    pub explosion: f32,
+}
+
+impl Default for Resistances {
+    fn default() -> Self {
+        Self { physical: 1., fire: 1., poison: 1., explosion: 1. }
+    }
+}
+
+impl Resistances {
+    fn multiplier(&self, kind: DamageType) -> f32 {
+        match kind {
+            DamageType::Physical => self.physical,
+            DamageType::Fire => self.fire,
+            DamageType::Poison => self.poison,
+            DamageType::Explosion => self.explosion,
+        }
+    }
+}
+
+/// Chance for hits from this entity to deal `multiplier` times their damage.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Critical {
+    pub chance: f64,
+    pub multiplier: i32,
+}
+
+/// Grants a window of invulnerability of this many seconds after taking damage.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct IFrames(pub f32);
+
+#[derive(Component, Debug)]
+#[component(storage = "SparseSet")]
+pub struct Invulnerable {
+    pub until: f32,
+}
+
+/// Last entity to damage this one, credited for kills by damage that has no source.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct LastHit(pub Entity);
+
+/// Points awarded for killing this entity, regular enemies are worth 1.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Bounty(pub i32);
+
+/// Entities that can take damage, and the critical chances of whoever deals it.
+#[derive(SystemParam)]
+struct Combatants<'w, 's> {
+    targets: Query<'w, 's, Target, Without<Death>>,
+    criticals: Query<'w, 's, &'static Critical>,
+}
+
+fn process_damage(
+    mut commands: Commands,
+    mut damage_events: EventReader<DamageEvent>,
+    mut combatants: Combatants,
+    mut enemy_killed_events: EventWriter<EnemyKilledEvent>,
+    mut status_events: EventWriter<ApplyStatusEvent>,
+    time: Res<Time>,
+) {
+    let mut rng = rand::thread_rng();
+    let now = time.elapsed_seconds();
+    let mut lifesteal = Vec::new();
+    let mut invulnerable_from_now = Vec::new();
+
+    for event in damage_events.read() {
+        let Ok((mut health, resistances, iframes, invulnerable, mut elite, bounty, last_hit, is_enemy)) = combatants.targets.get_mut(event.target) else {
+            continue;
+        };
+        if health.is_dead() || invulnerable.is_some_and(|invulnerable| now < invulnerable.until) {
+            continue;
+        }
+        // Several hits on one frame only count once against i-frames.
+        if invulnerable_from_now.contains(&event.target) {
+            continue;
+        }
+        if !event.over_time && elite.as_mut().is_some_and(|elite| elite.absorb()) {
+            continue;
+        }
+
+        let mut amount = event.amount.max(0);
+        if let Some(critical) = event.source.and_then(|source| combatants.criticals.get(source).ok()) {
+            if rng.gen_bool(critical.chance.clamp(0., 1.)) {
+                amount = amount.saturating_mul(critical.multiplier);
+            }
+        }
+        let multiplier = resistances.map_or(1., |resistances| resistances.multiplier(event.kind));
+        let amount = (amount as f32 * multiplier.max(0.)).round() as i32;
+
+        let taken = health.damage(amount);
+        if taken == 0 {
+            continue;
+        }
+
+        if let Some((kind, duration)) = event.on_hit {
+            status_events.send(ApplyStatusEvent { target: event.target, kind, duration });
+        }
+        if let Some(iframes) = iframes {
+            commands.entity(event.target).insert(Invulnerable { until: now + iframes.0 });
+            invulnerable_from_now.push(event.target);
+        }
+        if is_enemy {
+            // Burns and poison carry no source, so their kills go to whoever hit last.
+            if let Some(source) = event.source {
+                commands.entity(event.target).insert(LastHit(source));
+            }
+            if health.is_dead() {
+                enemy_killed_events.send(EnemyKilledEvent {
+                    points: bounty.map_or(1, |bounty| bounty.0),
+                    killer: event.source.or(last_hit.map(|hit| hit.0)),
+                });
+            }
+        }
+        if let Some(source) = event.source {
+            lifesteal.push(source);
+        }
+    }
+
+    for source in lifesteal {
+        if let Ok((mut health, _, _, _, Some(elite), ..)) = combatants.targets.get_mut(source) {
+            health.heal(elite.lifesteal());
+        }
+    }
+}
+
+fn blink_invulnerable(
+    mut commands: Commands,
+    mut invulnerable_query: Query<(Entity, &Invulnerable, &mut Visibility)>,
+    time: Res<Time>,
+) {
+    for (entity, invulnerable, mut visibility) in &mut invulnerable_query {
+        if time.elapsed_seconds() >= invulnerable.until {
+            *visibility = Visibility::Inherited;
+            commands.entity(entity).remove::<Invulnerable>();
+        } else if (time.elapsed_seconds() * BLINK_RATE) as i32 % 2 == 0 {
+            *visibility = Visibility::Hidden;
+        } else {
+            *visibility = Visibility::Inherited;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::elite::Affix;
+
+    use super::*;
+
+    fn app() -> App {
+        let mut app = App::new();
+        app
+            .init_resource::<Time>()
+            .add_event::<DamageEvent>()
+            .add_event::<EnemyKilledEvent>()
+            .add_event::<ApplyStatusEvent>()
+            .add_systems(Update, process_damage);
+        app
+    }
+
+    fn hit(app: &mut App, target: Entity, source: Option<Entity>, amount: i32, over_time: bool) {
+        app.world_mut().send_event(DamageEvent {
+            target,
+            source,
+            amount,
+            kind: DamageType::Physical,
+            on_hit: None,
+            over_time,
+        });
+    }
+
+    fn health(app: &App, entity: Entity) -> i32 {
+        app.world().get::<Health>(entity).unwrap().current()
+    }
+
+    #[test]
+    fn resistances_scale_damage() {
+        let mut app = app();
+        let target = app.world_mut().spawn((Health::new(10), Resistances { physical: 0.5, ..default() })).id();
+        hit(&mut app, target, None, 4, false);
+        app.update();
+        assert_eq!(health(&app, target), 8);
+    }
+
+    #[test]
+    fn critical_hits_multiply_damage() {
+        let mut app = app();
+        let source = app.world_mut().spawn(Critical { chance: 1., multiplier: 3 }).id();
+        let target = app.world_mut().spawn(Health::new(10)).id();
+        hit(&mut app, target, Some(source), 2, false);
+        app.update();
+        assert_eq!(health(&app, target), 4);
+    }
+
+    #[test]
+    fn iframes_ignore_hits_until_they_run_out() {
+        let mut app = app();
+        let target = app.world_mut().spawn((Health::new(10), IFrames(1.))).id();
+        hit(&mut app, target, None, 1, false);
+        hit(&mut app, target, None, 1, false);
+        app.update();
+        assert_eq!(health(&app, target), 9);
+
+        hit(&mut app, target, None, 1, false);
+        app.update();
+        assert_eq!(health(&app, target), 9);
+
+        app.world_mut().resource_mut::<Time>().advance_by(std::time::Duration::from_secs(2));
+        hit(&mut app, target, None, 1, false);
+        app.update();
+        assert_eq!(health(&app, target), 8);
+    }
+
+    #[test]
+    fn shields_absorb_hits_but_not_damage_over_time() {
+        let mut app = app();
+        let target = app.world_mut().spawn((Health::new(10), Elite::new(vec![Affix::Shielded]))).id();
+        hit(&mut app, target, None, 1, true);
+        app.update();
+        assert_eq!(health(&app, target), 9);
+
+        hit(&mut app, target, None, 1, false);
+        hit(&mut app, target, None, 1, false);
+        hit(&mut app, target, None, 1, false);
+        app.update();
+        assert_eq!(health(&app, target), 8);
+    }
+
+    #[test]
+    fn killing_an_enemy_credits_the_last_hit() {
+        let mut app = app();
+        let player = app.world_mut().spawn_empty().id();
+        let target = app.world_mut().spawn((Health::new(2), Enemy, Bounty(5))).id();
+        hit(&mut app, target, Some(player), 1, false);
+        app.update();
+        hit(&mut app, target, None, 1, true);
+        app.update();
+
+        let events = app.world().resource::<Events<EnemyKilledEvent>>();
+        let kills: Vec<_> = events.get_reader().read(events).map(|event| (event.points, event.killer)).collect();
+        assert_eq!(kills, vec![(5, Some(player))]);
+    }
+}
@@ -0,0 +1,171 @@
+use std::time::Duration;
+
+use bevy::{prelude::*, sprite::Mesh2dHandle};
+
+use crate::{damage::Invulnerable, input::{DeviceInputs, InputAction, InputDevice}, kinematics::{integrate_motion, Velocity}, player::{move_player, movement_input, Player}, status::StatusEffects, AppState, RunState};
+
+const DASH_SPEED: f32 = 900.;
+const DASH_TIME: f32 = 0.15;
+const DASH_COOLDOWN: f32 = 1.5;
+/// Invulnerability granted by a dash, a little longer than the dash itself.
+const DASH_IFRAMES: f32 = 0.3;
+const AFTERIMAGE_INTERVAL: f32 = 0.03;
+const AFTERIMAGE_LIFETIME: f32 = 0.25;
+const AFTERIMAGE_ALPHA: f32 = 0.5;
+
+type Dasher = (
+    Entity,
+    &'static InputDevice,
+    &'static Transform,
+    &'static mut Velocity,
+    &'static mut Dash,
+    &'static Mesh2dHandle,
+    &'static Handle<ColorMaterial>,
+    Option<&'static Invulnerable>,
+    Option<&'static StatusEffects>,
+);
+
+pub struct DashPlugin;
+
+impl Plugin for DashPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(Update, (dash_player.before(move_player).before(integrate_motion), fade_afterimages).run_if(in_state(RunState::Running)))
+            .add_systems(OnExit(AppState::InGame), despawn_afterimages)
+        ;
+    }
+}
+
+/// Short burst along the movement direction, with charges that refill one at a time.
+#[derive(Component, Debug)]
+pub struct Dash {
+    pub max_charges: u32,
+    /// Seconds for one charge to refill, upgrades lower it.
+    pub cooldown: f32,
+    charges: u32,
+    recharge: Timer,
+    direction: Vec3,
+    dashing_until: f32,
+    next_afterimage: f32,
+}
+
+impl Default for Dash {
+    fn default() -> Self {
+        Self {
+            max_charges: 1,
+            cooldown: DASH_COOLDOWN,
+            charges: 1,
+            recharge: Timer::from_seconds(DASH_COOLDOWN, TimerMode::Repeating),
+            direction: Vec3::X,
+            dashing_until: 0.,
+            next_afterimage: 0.,
+        }
+    }
+}
+
+impl Dash {
+    /// Starts with `max_charges` charges, all of them ready.
+    pub fn with_charges(max_charges: u32) -> Self {
+        Self { max_charges, charges: max_charges, ..default() }
+    }
+
+    pub fn is_dashing(&self, now: f32) -> bool {
+        now < self.dashing_until
+    }
+
+    pub fn charges(&self) -> u32 {
+        self.charges
+    }
+
+    /// Progress towards the next charge, 1 when every charge is available.
+    pub fn recharge_fraction(&self) -> f32 {
+        if self.charges >= self.max_charges { 1. } else { self.recharge.fraction() }
+    }
+}
+
+#[derive(Component)]
+struct Afterimage {
+    spawn_time: f32,
+}
+
+fn dash_player(
+    mut commands: Commands,
+    inputs: Res<DeviceInputs>,
+    mut player_query: Query<Dasher, With<Player>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    time: Res<Time>,
+) {
+    let now = time.elapsed_seconds();
+
+    for (player_entity, device, transform, mut velocity, mut dash, mesh, material_handle, invulnerable, status) in &mut player_query {
+        if dash.charges < dash.max_charges {
+            let cooldown = dash.cooldown;
+            dash.recharge.set_duration(Duration::from_secs_f32(cooldown));
+            if dash.recharge.tick(time.delta()).just_finished() {
+                dash.charges += 1;
+            }
+        } else {
+            dash.recharge.reset();
+        }
+
+        let input = inputs.get(*device);
+        let direction = movement_input(&input.actions, &input.analog);
+        if direction != Vec3::ZERO && !dash.is_dashing(now) {
+            dash.direction = direction.normalize();
+        }
+
+        let can_move = status.is_none_or(|status| status.speed_multiplier() > 0.);
+        if input.actions.just_pressed(InputAction::Dash) && dash.charges > 0 && !dash.is_dashing(now) && can_move {
+            dash.charges -= 1;
+            dash.dashing_until = now + DASH_TIME;
+            dash.next_afterimage = now;
+            // Never cut short i-frames the player already has from being hit.
+            let until = invulnerable.map_or(0., |invulnerable| invulnerable.until).max(now + DASH_IFRAMES);
+            commands.entity(player_entity).insert(Invulnerable { until });
+        }
+
+        if !dash.is_dashing(now) {
+            continue;
+        }
+        velocity.0 = dash.direction.truncate() * DASH_SPEED;
+
+        if now >= dash.next_afterimage {
+            dash.next_afterimage = now + AFTERIMAGE_INTERVAL;
+            let color = materials.get(material_handle).map_or(Color::WHITE, |material| material.color);
+            commands.spawn((
+                ColorMesh2dBundle {
+                    mesh: mesh.clone(),
+                    material: materials.add(color.with_alpha(AFTERIMAGE_ALPHA)),
+                    transform: transform.with_translation(transform.translation.with_z(transform.translation.z - 0.1)),
+                    ..default()
+                },
+                Afterimage { spawn_time: now },
+            ));
+        }
+    }
+}
+
+fn fade_afterimages(
+    mut commands: Commands,
+    afterimages_query: Query<(Entity, &Afterimage, &Handle<ColorMaterial>)>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    time: Res<Time>,
+) {
+    for (entity, afterimage, material_handle) in &afterimages_query {
+        let age = time.elapsed_seconds() - afterimage.spawn_time;
+        if age >= AFTERIMAGE_LIFETIME {
+            commands.entity(entity).despawn();
+        } else if let Some(material) = materials.get_mut(material_handle) {
+            material.color.set_alpha(AFTERIMAGE_ALPHA * (1. - age / AFTERIMAGE_LIFETIME));
+        }
+    }
+}
+
+fn despawn_afterimages(
+    mut commands: Commands,
+    afterimages_query: Query<Entity, With<Afterimage>>,
+) {
+    for entity in &afterimages_query {
+        commands.entity(entity).despawn();
+    }
+}
@@ -1,6 +1,6 @@
 use bevy::prelude::*;
 
-use crate::AppState;
+use crate::{input::InputAction, AppState};
 
 const NORMAL_BUTTON: Color = Color::srgb(0.15, 0.15, 0.15);
 const HOVERED_BUTTON: Color = Color::srgb(0.25, 0.25, 0.25);
@@ -13,6 +13,7 @@ impl Plugin for DeathPlugin {
         app
             .add_systems(OnEnter(AppState::EndGame), setup_death)
             .add_systems(Update, death_menu.run_if(in_state(AppState::EndGame)))
+            .add_systems(OnExit(AppState::EndGame), cleanup_death)
         ;
     }
 }
@@ -31,8 +32,10 @@ fn setup_death(
                 // center button
                 width: Val::Percent(100.),
                 height: Val::Percent(100.),
+                flex_direction: FlexDirection::Column,
                 justify_content: JustifyContent::Center,
                 align_items: AlignItems::Center,
+                row_gap: Val::Px(20.),
                 ..default()
             },
             ..default()
@@ -62,6 +65,15 @@ fn setup_death(
                         },
                     ));
                 });
+
+            parent.spawn(TextBundle::from_section(
+                "Quit to exit",
+                TextStyle {
+                    font_size: 20.0,
+                    color: Color::srgb(0.6, 0.6, 0.6),
+                    ..default()
+                },
+            ));
         })
         .id();
     commands.insert_resource(MenuData { button_entity });
@@ -73,7 +85,14 @@ fn death_menu(
         (&Interaction, &mut UiImage),
         (Changed<Interaction>, With<Button>),
     >,
+    actions: Res<ButtonInput<InputAction>>,
 ) {
+    // Confirm shares buttons with jump and pause, so only Quit presses the button.
+    if actions.just_pressed(InputAction::Quit) {
+        exit.send(AppExit::Success);
+        return;
+    }
+
     for (interaction, mut image) in &mut interaction_query {
         match *interaction {
             Interaction::Pressed => {
@@ -88,4 +107,8 @@ fn death_menu(
             }
         }
     }
-}
\ No newline at end of file
+}
+
+fn cleanup_death(mut commands: Commands, menu_data: Res<MenuData>) {
+    commands.entity(menu_data.button_entity).despawn_recursive();
+}
@@ -0,0 +1,217 @@
+use bevy::{color::palettes::css::{AQUA, CRIMSON, DARK_ORANGE, FOREST_GREEN, SILVER, YELLOW}, prelude::*};
+use rand::{seq::SliceRandom, Rng};
+
+use crate::{damage::{DamageEvent, DamageType, Resistances}, enemies::{Death, Health, Knockback}, player::Player, steering::Steering, AppState, RunState};
+
+const ELITE_CHANCE: f64 = 0.1;
+const EXTRA_AFFIX_CHANCE: f64 = 0.3;
+pub const ELITE_SIZE: f32 = 1.3;
+const FAST_MULTIPLIER: f32 = 1.8;
+const ARMOURED_HEALTH: i32 = 2;
+const ARMOURED_RESISTANCE: f32 = 0.3;
+const ARMOURED_PHYSICAL: f32 = 0.5;
+/// Regenerating elites get extra health so they live long enough for healing to matter.
+const REGENERATING_HEALTH: i32 = 3;
+const REGEN_INTERVAL: f32 = 2.;
+const SHIELD_HITS: i32 = 2;
+const EXPLOSION_RADIUS: f32 = 120.;
+const VAMPIRIC_HEAL: i32 = 2;
+
+pub struct ElitePlugin;
+
+impl Plugin for ElitePlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(Update, (regenerate_elites, explode_elites).run_if(in_state(RunState::Running)))
+            .add_systems(Update, draw_shields.run_if(in_state(AppState::InGame)))
+        ;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Affix {
+    Fast,
+    Armoured,
+    Regenerating,
+    /// Absorbs the first few hits before any damage reaches `Health`.
+    Shielded,
+    /// Hurts the player if they are close when it dies.
+    Explosive,
+    /// Heals whenever it damages the player.
+    Vampiric,
+}
+
+const AFFIXES: [Affix; 6] = [Affix::Fast, Affix::Armoured, Affix::Regenerating, Affix::Shielded, Affix::Explosive, Affix::Vampiric];
+
+impl Affix {
+    fn color(self) -> Srgba {
+        match self {
+            Affix::Fast => YELLOW,
+            Affix::Armoured => SILVER,
+            Affix::Regenerating => FOREST_GREEN,
+            Affix::Shielded => AQUA,
+            Affix::Explosive => DARK_ORANGE,
+            Affix::Vampiric => CRIMSON,
+        }
+    }
+}
+
+/// Modifiers rolled on top of a regular enemy's components.
+#[derive(Component, Debug)]
+pub struct Elite {
+    affixes: Vec<Affix>,
+    shield: i32,
+    regen_timer: Timer,
+}
+
+impl Elite {
+    /// Most spawns stay regular; the rest get one affix, sometimes two.
+    pub fn roll(rng: &mut impl Rng) -> Option<Self> {
+        if !rng.gen_bool(ELITE_CHANCE) {
+            return None;
+        }
+
+        let count = if rng.gen_bool(EXTRA_AFFIX_CHANCE) { 2 } else { 1 };
+        Some(Self::new(AFFIXES.choose_multiple(rng, count).copied().collect()))
+    }
+
+    pub fn new(affixes: Vec<Affix>) -> Self {
+        let shield = if affixes.contains(&Affix::Shielded) { SHIELD_HITS } else { 0 };
+        Self {
+            affixes,
+            shield,
+            regen_timer: Timer::from_seconds(REGEN_INTERVAL, TimerMode::Repeating),
+        }
+    }
+
+    pub fn has(&self, affix: Affix) -> bool {
+        self.affixes.contains(&affix)
+    }
+
+    /// Scales the base stats of the enemy the affixes are rolled onto.
+    pub fn apply(&self, health: &mut Health, steering: &mut Steering, knockback: &mut Knockback) {
+        if self.has(Affix::Fast) {
+            steering.max_speed *= FAST_MULTIPLIER;
+            steering.max_force *= FAST_MULTIPLIER;
+        }
+        if self.has(Affix::Armoured) {
+            *health = Health::new(health.max() + ARMOURED_HEALTH);
+            knockback.resistance = (knockback.resistance + ARMOURED_RESISTANCE).min(1.);
+        }
+        if self.has(Affix::Regenerating) {
+            *health = Health::new(health.max() + REGENERATING_HEALTH);
+        }
+    }
+
+    pub fn resistances(&self) -> Resistances {
+        if self.has(Affix::Armoured) {
+            Resistances { physical: ARMOURED_PHYSICAL, ..default() }
+        } else {
+            Resistances::default()
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        Color::from(self.affixes[0].color())
+    }
+
+    /// Score for the kill, growing with the number of affixes.
+    pub fn points(&self) -> i32 {
+        1 + 2 * self.affixes.len() as i32
+    }
+
+    /// Spends one shield charge, returning whether the hit was absorbed.
+    pub fn absorb(&mut self) -> bool {
+        if self.shield <= 0 {
+            return false;
+        }
+        self.shield -= 1;
+        true
+    }
+
+    /// Health regained after damaging something.
+    pub fn lifesteal(&self) -> i32 {
+        if self.has(Affix::Vampiric) { VAMPIRIC_HEAL } else { 0 }
+    }
+}
+
+fn regenerate_elites(
+    mut elites_query: Query<(&mut Elite, &mut Health), Without<Death>>,
+    time: Res<Time>,
+) {
+    for (mut elite, mut health) in &mut elites_query {
+        if !elite.has(Affix::Regenerating) || !elite.regen_timer.tick(time.delta()).just_finished() {
+            continue;
+        }
+        health.heal(1);
+    }
+}
+
+fn explode_elites(
+    elites_query: Query<(Entity, &Elite, &Transform), Added<Death>>,
+    player_query: Query<(Entity, &Transform), With<Player>>,
+    mut damage_events: EventWriter<DamageEvent>,
+) {
+    for (elite_entity, elite, transform) in &elites_query {
+        if !elite.has(Affix::Explosive) {
+            continue;
+        }
+        for (player_entity, player_transform) in &player_query {
+            if transform.translation.truncate().distance(player_transform.translation.truncate()) <= EXPLOSION_RADIUS {
+                damage_events.send(DamageEvent {
+                    target: player_entity,
+                    source: Some(elite_entity),
+                    amount: 1,
+                    kind: DamageType::Explosion,
+                    on_hit: None,
+                    over_time: false,
+                });
+            }
+        }
+    }
+}
+
+fn draw_shields(
+    mut gizmos: Gizmos,
+    elites_query: Query<(&Elite, &Transform), Without<Death>>,
+) {
+    for (elite, transform) in &elites_query {
+        if elite.shield > 0 {
+            gizmos.circle_2d(transform.translation.truncate(), 20. * ELITE_SIZE + 8., Affix::Shielded.color());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    #[test]
+    fn regenerating_elites_get_extra_health() {
+        let mut health = Health::new(1);
+        Elite::new(vec![Affix::Regenerating]).apply(&mut health, &mut Steering::new(30.), &mut Knockback::new(0.));
+        assert_eq!(health.max(), 1 + REGENERATING_HEALTH);
+    }
+
+    #[test]
+    fn regenerating_elites_heal_over_time() {
+        let mut app = App::new();
+        app
+            .init_resource::<Time>()
+            .add_systems(Update, regenerate_elites);
+        let mut health = Health::new(4);
+        health.damage(3);
+        let regenerating = app.world_mut().spawn((Elite::new(vec![Affix::Regenerating]), health)).id();
+        let mut health = Health::new(4);
+        health.damage(3);
+        let fast = app.world_mut().spawn((Elite::new(vec![Affix::Fast]), health)).id();
+
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_secs_f32(REGEN_INTERVAL));
+        app.update();
+
+        assert_eq!(app.world().get::<Health>(regenerating).unwrap().current(), 2);
+        assert_eq!(app.world().get::<Health>(fast).unwrap().current(), 1);
+    }
+}
@@ -1,31 +1,57 @@
-use bevy::{math::bounding::*, color::palettes::css::{BLUE, RED}, prelude::*};
+use bevy::{math::bounding::*, color::palettes::css::BLUE, prelude::*, sprite::Mesh2dHandle};
 use rand::Rng;
 
-use crate::{bounding::{Intersects, Shape, Volume}, bullet::Bullet, player::{Player, PlayerHitEvent, PLAYER_SIZE}, AppState};
+use crate::{attack::Attack, behaviour::{tick_behaviours, Action, Archetype, Behaviour, BehaviourTrees}, bounding::{Intersects, Shape, Volume}, bullet::{Bullet, Hostile}, damage::{Bounty, DamageEvent, DamageType}, elite::{Elite, ELITE_SIZE}, formation::FormationMember, kinematics::{integrate_motion, Drag, Velocity}, level::{Obstacle, FLOOR_SIZE}, loot::DropTable, navigation::{update_flow_field, FlowField, NavGrid}, nest::Nest, perception::{Perception, WANDER_SPEED}, player::{nearest_player, Player, PLAYER_SIZE}, status::StatusEffects, steering::{update_spatial_grid, SpatialGrid, Steering}, AppState, RunState};
 
 const SPAWN_DELAY: f32 = 1.;
 const DEATH_TIME: f32 = 0.5;
 const SPEED: f32 = 30.;
+pub const TELEGRAPH_TIME: f32 = 1.;
+const TELEGRAPH_PULSE: f32 = 12.;
+/// Spawns land between this far and `SPAWN_MARGIN + SPAWN_BAND` outside the camera view.
+const SPAWN_MARGIN: f32 = 60.;
+const SPAWN_BAND: f32 = 250.;
+const SPAWN_ATTEMPTS: usize = 8;
+/// Enemies more than `FAR_MARGIN` outside the camera view drop to the coarse update rate,
+/// and come back within `NEAR_MARGIN`. Both clear the band regular spawns land in.
+const FAR_MARGIN: f32 = 500.;
+const NEAR_MARGIN: f32 = 400.;
+const FAR_INTERVAL: f32 = 0.25;
+const DESPAWN_MARGIN: f32 = 1800.;
+/// Regular enemies and pending spawns allowed at once.
+pub const MAX_ENEMIES: usize = 150;
+const STUN_TIME: f32 = 0.3;
+const KNOCKBACK_DAMPING: f32 = 6.;
 
 pub struct EnemyPlugin;
 
 impl Plugin for EnemyPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Update, (
+        app
+        .init_resource::<TelegraphAssets>()
+        .add_systems(Update, (
             spawn_enemy.run_if(time_passed(SPAWN_DELAY)),
-            move_enemies,
+            hatch_telegraphs,
+            update_enemy_lod.before(move_enemies).before(tick_behaviours),
+            move_enemies.after(update_spatial_grid).after(update_flow_field).after(tick_behaviours).before(integrate_motion),
             (
-                (enemy_bullet_collision, enemy_player_collision),
+                (enemy_bullet_collision, enemy_player_collision, hostile_bullet_player_collision),
                 play_death,
                 despawn_dead
             ).chain()
-        ).run_if(in_state(AppState::InGame)))
+        ).run_if(in_state(RunState::Running)))
+        .add_systems(OnExit(AppState::InGame), despawn_enemies)
         .add_event::<EnemyKilledEvent>();
     }
 }
 
-#[derive(Event, Default)]
-pub struct EnemyKilledEvent;
+/// Sent by the damage pipeline when an enemy is killed, carrying the score it is worth.
+#[derive(Event)]
+pub struct EnemyKilledEvent {
+    pub points: i32,
+    /// Player credited with the kill, if one is known.
+    pub killer: Option<Entity>,
+}
 
 fn time_passed(t: f32) -> impl FnMut(Local<f32>, Res<Time>) -> bool {
     move |mut timer: Local<f32>, time: Res<Time>| {
@@ -37,73 +63,299 @@ fn time_passed(t: f32) -> impl FnMut(Local<f32>, Res<Time>) -> bool {
 }
 
 #[derive(Component)]
-pub struct Enemy {
-    death_material: Handle<ColorMaterial>
+pub struct Enemy;
+
+/// Marks enemies far from the player: they move in coarse steps and skip collision.
+#[derive(Component)]
+#[component(storage = "SparseSet")]
+pub struct Far {
+    pending: f32,
+}
+
+impl Far {
+    /// Accumulates `delta` and hands it back once a full interval has built up.
+    fn tick(&mut self, delta: f32) -> Option<f32> {
+        self.pending += delta;
+        (self.pending >= FAR_INTERVAL).then(|| std::mem::take(&mut self.pending))
+    }
+}
+
+/// Warning marker left where an enemy of `archetype` will appear at `spawn_time`.
+#[derive(Component)]
+pub struct SpawnTelegraph {
+    archetype: Archetype,
+    spawn_time: f32,
+    /// Slot the enemy takes in a formation once it hatches.
+    pub formation: Option<FormationMember>,
+}
+
+impl SpawnTelegraph {
+    pub fn new(archetype: Archetype, spawn_time: f32) -> Self {
+        Self { archetype, spawn_time, formation: None }
+    }
+}
+
+/// Shared mesh and material for every spawn telegraph.
+#[derive(Resource)]
+pub struct TelegraphAssets {
+    mesh: Mesh2dHandle,
+    material: Handle<ColorMaterial>,
+}
+
+impl TelegraphAssets {
+    pub fn bundle(&self, position: Vec2) -> ColorMesh2dBundle {
+        ColorMesh2dBundle {
+            mesh: self.mesh.clone(),
+            material: self.material.clone(),
+            transform: Transform::from_translation(position.extend(-0.02)),
+            ..default()
+        }
+    }
 }
 
-#[derive(Component, Default, Debug)]
-pub struct Health(pub i8);
+impl FromWorld for TelegraphAssets {
+    fn from_world(world: &mut World) -> Self {
+        let mesh = world.resource_mut::<Assets<Mesh>>().add(Annulus::new(16., 22.)).into();
+        let material = world.resource_mut::<Assets<ColorMaterial>>().add(Color::srgba(1., 0.2, 0.2, 0.7));
+        Self { mesh, material }
+    }
+}
+
+/// Current and maximum hit points, only ever changed through `damage` and `heal`.
+#[derive(Component, Debug)]
+pub struct Health {
+    current: i32,
+    max: i32,
+}
 
 #[derive(Component, Default)]
 pub struct Death(f32);
 
+/// Query filter for enemies that have not started dying.
+pub type LiveEnemy = (With<Enemy>, Without<Death>);
+/// Regular enemies and pending spawns, counted against `MAX_ENEMIES`.
+pub type CappedEnemy = Or<(With<Behaviour>, With<SpawnTelegraph>)>;
+/// Live enemies close enough to run the full update.
+pub type NearEnemy = (With<Enemy>, Without<Death>, Without<Far>);
+/// Enemies and pending spawns a finished run leaves behind, nests clear themselves.
+type RunEnemy = (Or<(With<Enemy>, With<SpawnTelegraph>)>, Without<Nest>);
+type LodCandidate = (With<Behaviour>, Without<Death>, Without<Player>);
+type EnemyMover = (
+    Entity,
+    &'static Transform,
+    &'static mut Steering,
+    &'static mut Velocity,
+    &'static Knockback,
+    &'static Behaviour,
+    Option<&'static mut Far>,
+    Option<&'static StatusEffects>,
+    Option<&'static FormationMember>,
+    Option<&'static Attack>,
+    Option<&'static Perception>,
+);
+type BulletTarget = (Entity, &'static Volume, Option<(&'static mut Knockback, &'static mut Velocity)>);
+
+/// Pushback from hits; while stunned the enemy drifts on its velocity instead of steering.
+#[derive(Component)]
+pub struct Knockback {
+    /// 0 takes every impulse in full, 1 is immune.
+    pub resistance: f32,
+    stunned_until: f32,
+}
+
+impl Knockback {
+    pub fn new(resistance: f32) -> Self {
+        Self {
+            resistance: resistance.clamp(0., 1.),
+            stunned_until: f32::NEG_INFINITY,
+        }
+    }
+
+    pub fn push(&mut self, velocity: &mut Velocity, impulse: Vec2, now: f32) {
+        let scale = 1. - self.resistance;
+        if scale <= 0. {
+            return;
+        }
+        velocity.0 += impulse * scale;
+        self.stunned_until = self.stunned_until.max(now + STUN_TIME * scale);
+    }
+
+    pub fn is_stunned(&self, now: f32) -> bool {
+        now < self.stunned_until
+    }
+}
+
 impl Health {
-    pub fn hit(&mut self, hit_points: i8) {
-        self.0 -= hit_points;
+    pub fn new(max: i32) -> Self {
+        let max = max.max(1);
+        Self { current: max, max }
+    }
+
+    pub fn current(&self) -> i32 {
+        self.current
+    }
+
+    pub fn max(&self) -> i32 {
+        self.max
+    }
+
+    pub fn fraction(&self) -> f32 {
+        self.current as f32 / self.max as f32
+    }
+
+    pub fn is_dead(&self) -> bool {
+        self.current <= 0
+    }
+
+    /// Removes up to `amount` without going below zero and returns how much was taken.
+    pub fn damage(&mut self, amount: i32) -> i32 {
+        let taken = amount.clamp(0, self.current);
+        self.current -= taken;
+        taken
+    }
+
+    /// Changes the maximum. Raising it keeps the damage already taken, lowering it only cuts
+    /// health above the new maximum and never kills.
+    pub fn set_max(&mut self, max: i32) {
+        let max = max.max(1);
+        if self.current > 0 {
+            self.current = if max > self.max { self.current + max - self.max } else { self.current.clamp(1, max) };
+        }
+        self.max = max;
+    }
+
+    /// Restores up to `amount` without going above max and returns how much was healed.
+    pub fn heal(&mut self, amount: i32) -> i32 {
+        let healed = amount.clamp(0, self.max - self.current);
+        self.current += healed;
+        healed
     }
 }
 
 fn spawn_enemy(
     mut commands: Commands,
-    mut meshes: ResMut<Assets<Mesh>>,
-    mut materials: ResMut<Assets<ColorMaterial>>,
-    mut player_query: Query<&Transform, With<Player>>,
+    enemies_query: Query<(), CappedEnemy>,
+    player_query: Query<&Transform, With<Player>>,
+    camera_query: Query<(&Transform, &OrthographicProjection), With<Camera2d>>,
+    nav_grid: Res<NavGrid>,
+    telegraph_assets: Res<TelegraphAssets>,
+    time: Res<Time>,
 ) {
-    let Ok(player_transform) = player_query.get_single_mut() else {
+    if enemies_query.iter().count() >= MAX_ENEMIES {
+        return;
+    }
+    let players: Vec<_> = player_query.iter().map(|transform| transform.translation.truncate()).collect();
+    if players.is_empty() {
+        return;
+    }
+    let Ok((camera_transform, projection)) = camera_query.get_single() else {
         return;
     };
 
     let mut rng = rand::thread_rng();
-    let mut position = player_transform.translation;
-    
-    loop {
-        position = player_transform.translation + Vec3::new(
-           rng.gen_range(-800.0..800.),
-           rng.gen_range(-800.0..800.),
-           0.,
-       );
-
-       if player_transform.translation.distance_squared(position) > 8. * PLAYER_SIZE * PLAYER_SIZE {
-            break;
-       }
+    let view = Rect::from_center_size(
+        camera_transform.translation.truncate() + projection.area.center(),
+        projection.area.size(),
+    );
+    let Some(position) = spawn_position(view, &players, &nav_grid, &mut rng) else {
+        return;
+    };
+
+    let archetype = match rng.gen_range(0..10) {
+        0 => Archetype::Skirmisher,
+        1 | 2 => Archetype::Flanker,
+        _ => Archetype::Chaser,
+    };
+
+    commands.spawn((telegraph_assets.bundle(position), SpawnTelegraph::new(archetype, time.elapsed_seconds() + TELEGRAPH_TIME)));
+}
+
+/// Picks a walkable point on the floor away from every player, preferring a band just outside the camera view.
+pub fn spawn_position(view: Rect, players: &[Vec2], nav_grid: &NavGrid, rng: &mut impl Rng) -> Option<Vec2> {
+    let arena = Rect::from_center_size(Vec2::ZERO, Vec2::splat(FLOOR_SIZE - 2. * SPAWN_MARGIN));
+
+    for _ in 0..SPAWN_ATTEMPTS {
+        let ring = view.inflate(rng.gen_range(SPAWN_MARGIN..SPAWN_MARGIN + SPAWN_BAND));
+        let point = match rng.gen_range(0..4) {
+            0 => Vec2::new(rng.gen_range(ring.min.x..ring.max.x), ring.min.y),
+            1 => Vec2::new(rng.gen_range(ring.min.x..ring.max.x), ring.max.y),
+            2 => Vec2::new(ring.min.x, rng.gen_range(ring.min.y..ring.max.y)),
+            _ => Vec2::new(ring.max.x, rng.gen_range(ring.min.y..ring.max.y)),
+        };
+        // Near the edge of the floor the band gets pulled back inside, possibly into view.
+        let point = point.clamp(arena.min, arena.max);
+
+        if players.iter().all(|player| point.distance_squared(*player) > 8. * PLAYER_SIZE * PLAYER_SIZE)
+            && nav_grid.cell(point).is_some_and(|cell| nav_grid.is_walkable(cell)) {
+            return Some(point);
+        }
     }
+    None
+}
 
-    let triangle_primitive = Triangle2d::new(
-        Vec2::Y * 20.0,
-        Vec2::new(-20.0, -20.0),
-        Vec2::new(20.0, -20.0),
-    );
+fn hatch_telegraphs(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    mut telegraphs_query: Query<(Entity, &mut Transform, &SpawnTelegraph)>,
+    trees: Res<BehaviourTrees>,
+    time: Res<Time>,
+) {
+    let mut rng = rand::thread_rng();
+    for (telegraph_entity, mut transform, telegraph) in &mut telegraphs_query {
+        let remaining = telegraph.spawn_time - time.elapsed_seconds();
+        if remaining > 0. {
+            transform.scale = Vec3::splat(1. + 0.2 * (remaining * TELEGRAPH_PULSE).sin());
+            continue;
+        }
+        commands.entity(telegraph_entity).despawn();
 
-    commands.spawn((ColorMesh2dBundle {
-        mesh: meshes.add(triangle_primitive).into(),
-        material: materials.add(Color::from(BLUE)),
-        transform: Transform::from_translation(position),
-        ..default()
-    }, Enemy {
-        death_material: materials.add(Color::from(RED)),
-    }, Health(1), Shape::Triangle(triangle_primitive), Intersects::default()));
+        let archetype = telegraph.archetype;
+        let mut health = Health::new(1);
+        let mut steering = Steering::new(SPEED);
+        let mut knockback = Knockback::new(archetype.knockback_resistance());
+        let elite = Elite::roll(&mut rng);
+        if let Some(elite) = elite.as_ref() {
+            elite.apply(&mut health, &mut steering, &mut knockback);
+        }
+        let size = if elite.is_some() { 20. * ELITE_SIZE } else { 20. };
+        let color = elite.as_ref().map_or(Color::from(BLUE), Elite::color);
+
+        let triangle_primitive = Triangle2d::new(
+            Vec2::Y * size,
+            Vec2::new(-size, -size),
+            Vec2::new(size, -size),
+        );
+
+        let mut enemy = commands.spawn((ColorMesh2dBundle {
+            mesh: meshes.add(triangle_primitive).into(),
+            material: materials.add(color),
+            transform: Transform::from_translation(transform.translation.with_z(0.)),
+            ..default()
+        }, Enemy, health, Shape::Triangle(triangle_primitive), Intersects::default(), steering, knockback, Velocity::default(), Drag(KNOCKBACK_DAMPING), DropTable::for_archetype(archetype), trees.behaviour(archetype)));
+        if let Some(elite) = elite {
+            enemy.insert((Bounty(elite.points()), elite.resistances(), elite));
+        }
+        if let Some(member) = telegraph.formation {
+            enemy.insert(member);
+        }
+        enemy.insert(Perception::for_archetype(archetype));
+        if let Some(attack) = Attack::for_archetype(archetype) {
+            enemy.insert(attack);
+        }
+    }
 }
 
 pub fn play_death(
     mut commands: Commands, 
-    mut enemies_query: Query<(Entity, &Health, &Enemy), Without<Death>>,
+    mut enemies_query: Query<(Entity, &Health), LiveEnemy>,
     time: Res<Time>
 ) {
-    for (enemy_entity, enemy_health, enemy) in &mut enemies_query {
-        if enemy_health.0 <= 0 {
+    for (enemy_entity, enemy_health) in &mut enemies_query {
+        if enemy_health.is_dead() {
+            // The shatter effect takes over the visuals from here.
             commands.entity(enemy_entity).insert((
-                enemy.death_material.clone(),
-                
+                Visibility::Hidden,
                 Death(time.elapsed_seconds())
             ));
         }
@@ -122,37 +374,124 @@ pub fn despawn_dead(
     }
 }
 
-pub fn move_enemies(
-    mut enemies_query: Query<(Entity, &mut Transform), (With<Enemy>, Without<Death>, Without<Player>)>,
-    mut player_query: Query<(Entity, &Transform), With<Player>>,
-    time: Res<Time>
+fn update_enemy_lod(
+    mut commands: Commands,
+    enemies_query: Query<(Entity, &Transform, Has<Far>), LodCandidate>,
+    camera_query: Query<(&Transform, &OrthographicProjection), With<Camera2d>>,
 ) {
-    let Ok((player_entity, player_transform)) = player_query.get_single_mut() else {
+    let Ok((camera_transform, projection)) = camera_query.get_single() else {
         return;
     };
+    // The shared camera zooms out to keep every player in view, so distances are measured from what is on screen.
+    let view = Rect::from_center_size(
+        camera_transform.translation.truncate() + projection.area.center(),
+        projection.area.size(),
+    );
+
+    let mut rng = rand::thread_rng();
+    for (enemy_entity, transform, is_far) in &enemies_query {
+        let position = transform.translation.truncate();
+        let distance = position.distance(position.clamp(view.min, view.max));
+        if distance > DESPAWN_MARGIN {
+            // New spawns near the view replace enemies left this far behind.
+            commands.entity(enemy_entity).despawn();
+        } else if is_far && distance < NEAR_MARGIN {
+            commands.entity(enemy_entity).remove::<Far>();
+        } else if !is_far && distance > FAR_MARGIN {
+            // Random phase so far enemies do not all step on the same frame.
+            commands.entity(enemy_entity).insert(Far { pending: rng.gen_range(0.0..FAR_INTERVAL) });
+        }
+    }
+}
+
+pub fn move_enemies(
+    mut enemies_query: Query<EnemyMover, LiveEnemy>,
+    player_query: Query<(Entity, &Transform), With<Player>>,
+    obstacles_query: Query<&Volume, With<Obstacle>>,
+    grid: Res<SpatialGrid>,
+    nav_grid: Res<NavGrid>,
+    flow_field: Res<FlowField>,
+    time: Res<Time>
+) {
+    let players: Vec<_> = player_query.iter().map(|(entity, transform)| (entity, transform.translation.xy())).collect();
+    let obstacles: Vec<_> = obstacles_query.iter().map(|volume| volume.0).collect();
+
+    for (enemy_entity, enemy_transform, mut steering, mut velocity, knockback, behaviour, far, status, formation, attack, perception) in &mut enemies_query {
+        let delta = match far {
+            Some(mut far) => far.tick(time.delta_seconds()),
+            None => Some(time.delta_seconds()),
+        };
+
+        // The knockback impulse is already in the velocity, drag slows it down.
+        if knockback.is_stunned(time.elapsed_seconds()) {
+            steering.velocity = Vec2::ZERO;
+            continue;
+        }
 
-    for (enemy_entity, mut enemy_transform) in &mut enemies_query {
-        let mut dir = player_transform.translation - enemy_transform.translation;
-        dir = dir.normalize();
-        
-        enemy_transform.translation += dir * time.delta_seconds() * SPEED;
+        let mut speed_multiplier = status.map_or(1., StatusEffects::speed_multiplier);
+        if attack.is_some_and(Attack::is_busy) {
+            steering.velocity = Vec2::ZERO;
+            continue;
+        }
+        if speed_multiplier == 0. {
+            steering.velocity = Vec2::ZERO;
+            velocity.0 = Vec2::ZERO;
+            continue;
+        }
+
+        let position = enemy_transform.translation.xy();
+        let Some((_, player_position)) = nearest_player(&players, position) else {
+            steering.velocity = Vec2::ZERO;
+            velocity.0 = Vec2::ZERO;
+            continue;
+        };
+        let to_player = player_position - position;
+        let wander = perception.and_then(Perception::target);
+        if wander.is_some() {
+            speed_multiplier *= WANDER_SPEED;
+        }
+        // Between coarse updates far enemies keep going the way they last steered.
+        let Some(delta) = delta else {
+            velocity.0 = steering.velocity * speed_multiplier;
+            continue;
+        };
+        let target = match formation.and_then(|member| member.target).or(wander) {
+            Some(target) => target,
+            None => match behaviour.action {
+                Action::Chase => flow_field.waypoint(&nav_grid, position).unwrap_or(player_position),
+                Action::Flee => position - to_player,
+                Action::Strafe => position + to_player.perp() + to_player * 0.2,
+                Action::Wait(_) | Action::Shoot => position,
+            },
+        };
+        velocity.0 = steering.steer(enemy_entity, position, target, &grid, &obstacles, delta) * speed_multiplier;
     }
 }
 
 pub fn enemy_bullet_collision(
     mut commands: Commands,
-    bullets_query: Query<(Entity, &Volume), With<Bullet>>,
-    mut enemies_query: Query<(&mut Health, &Volume), (With<Enemy>, Without<Player>, Without<Death>)>,
-    mut player_killed_events: EventWriter<EnemyKilledEvent>
+    bullets_query: Query<(Entity, &Bullet, &Volume), Without<Hostile>>,
+    mut enemies_query: Query<BulletTarget, NearEnemy>,
+    mut damage_events: EventWriter<DamageEvent>,
+    time: Res<Time>
 ) {
-    for (mut enemy_health, enemy_volume) in &mut enemies_query {
+    for (enemy_entity, enemy_volume, mut knockback) in &mut enemies_query {
         let mut has_intersected = false;
-        for (bullet_entity, bullet_volume) in &bullets_query {
+        for (bullet_entity, bullet, bullet_volume) in &bullets_query {
             if enemy_volume.intersects(&bullet_volume.0) {
-                enemy_health.hit(1);
+                damage_events.send(DamageEvent {
+                    target: enemy_entity,
+                    source: bullet.source,
+                    amount: bullet.damage,
+                    kind: DamageType::Physical,
+                    on_hit: bullet.status,
+                    over_time: false,
+                });
+                if let Some((knockback, velocity)) = knockback.as_mut() {
+                    knockback.push(velocity, bullet.direction.truncate() * bullet.knockback, time.elapsed_seconds());
+                }
                 commands.entity(bullet_entity).despawn();
                 has_intersected = true;
-                player_killed_events.send_default();
                 break;
             }
         }
@@ -163,21 +502,142 @@ pub fn enemy_bullet_collision(
 }
 
 pub fn enemy_player_collision(
-    mut enemies_query: Query<(&mut Health, &Volume), (With<Enemy>, Without<Player>, Without<Death>)>,
-    mut player_query: Query<(&mut Health, &Volume), With<Player>>,
-    mut player_hit_events: EventWriter<PlayerHitEvent>
+    enemies_query: Query<(Entity, &Volume), NearEnemy>,
+    player_query: Query<(Entity, &Volume), With<Player>>,
+    mut damage_events: EventWriter<DamageEvent>
 ) {
+    for (player_entity, player_volume) in &player_query {
+        for (enemy_entity, enemy_volume) in &enemies_query {
+            if player_volume.intersects(&enemy_volume.0) {
+                damage_events.send(DamageEvent {
+                    target: player_entity,
+                    source: Some(enemy_entity),
+                    amount: 1,
+                    kind: DamageType::Physical,
+                    on_hit: None,
+                    over_time: false,
+                });
+                damage_events.send(DamageEvent {
+                    target: enemy_entity,
+                    source: Some(player_entity),
+                    amount: 1,
+                    kind: DamageType::Physical,
+                    on_hit: None,
+                    over_time: false,
+                });
+                break;
+            }
+        }
+    }
+}
 
-    let Ok((mut player_health, player_volume)) = player_query.get_single_mut() else {
-        return;
-    };
-
-    for (mut enemy_health, enemy_volume) in &mut enemies_query {
-        if player_volume.intersects(&enemy_volume.0) {
-            player_health.hit(1);
-            enemy_health.hit(1);
-            player_hit_events.send_default();
-            break;
+pub fn hostile_bullet_player_collision(
+    mut commands: Commands,
+    bullets_query: Query<(Entity, &Bullet, &Volume), With<Hostile>>,
+    player_query: Query<(Entity, &Volume), With<Player>>,
+    mut damage_events: EventWriter<DamageEvent>
+) {
+    let mut spent = Vec::new();
+    for (player_entity, player_volume) in &player_query {
+        for (bullet_entity, bullet, bullet_volume) in &bullets_query {
+            if !spent.contains(&bullet_entity) && player_volume.intersects(&bullet_volume.0) {
+                damage_events.send(DamageEvent {
+                    target: player_entity,
+                    source: bullet.source,
+                    amount: bullet.damage,
+                    kind: DamageType::Physical,
+                    on_hit: bullet.status,
+                    over_time: false,
+                });
+                commands.entity(bullet_entity).despawn();
+                spent.push(bullet_entity);
+                break;
+            }
         }
     }
-}
\ No newline at end of file
+}
+
+/// Clears enemies, bosses included, and telegraphed spawns when the run ends.
+fn despawn_enemies(
+    mut commands: Commands,
+    enemies_query: Query<Entity, RunEnemy>,
+) {
+    for entity in &enemies_query {
+        commands.entity(entity).despawn();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn damage_stops_at_zero() {
+        let mut health = Health::new(3);
+        assert_eq!(health.damage(2), 2);
+        assert_eq!(health.damage(5), 1);
+        assert_eq!(health.current(), 0);
+        assert!(health.is_dead());
+        assert_eq!(health.damage(1), 0);
+    }
+
+    #[test]
+    fn negative_amounts_are_ignored() {
+        let mut health = Health::new(3);
+        assert_eq!(health.damage(-4), 0);
+        assert_eq!(health.heal(-4), 0);
+        assert_eq!(health.current(), 3);
+    }
+
+    #[test]
+    fn damage_does_not_overflow() {
+        let mut health = Health::new(3);
+        assert_eq!(health.damage(i32::MAX), 3);
+        assert_eq!(health.current(), 0);
+    }
+
+    #[test]
+    fn heal_stops_at_max() {
+        let mut health = Health::new(5);
+        health.damage(3);
+        assert_eq!(health.heal(10), 3);
+        assert_eq!(health.current(), 5);
+    }
+
+    #[test]
+    fn max_is_at_least_one() {
+        assert_eq!(Health::new(0).max(), 1);
+        let mut health = Health::new(3);
+        health.set_max(-2);
+        assert_eq!(health.max(), 1);
+    }
+
+    #[test]
+    fn raising_max_keeps_damage_taken() {
+        let mut health = Health::new(5);
+        health.damage(2);
+        health.set_max(8);
+        assert_eq!((health.current(), health.max()), (6, 8));
+    }
+
+    #[test]
+    fn lowering_max_only_cuts_the_excess() {
+        let mut health = Health::new(8);
+        health.damage(2);
+        health.set_max(2);
+        assert_eq!((health.current(), health.max()), (2, 2));
+
+        let mut health = Health::new(5);
+        health.damage(4);
+        health.set_max(3);
+        assert_eq!((health.current(), health.max()), (1, 3));
+    }
+
+    #[test]
+    fn set_max_does_not_revive() {
+        let mut health = Health::new(2);
+        health.damage(2);
+        health.set_max(4);
+        assert!(health.is_dead());
+    }
+}
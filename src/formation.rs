@@ -0,0 +1,328 @@
+use std::f32::consts::TAU;
+
+use bevy::{ecs::system::SystemParam, prelude::*, utils::HashMap};
+use rand::{seq::SliceRandom, Rng};
+
+use crate::{behaviour::Archetype, enemies::{move_enemies, CappedEnemy, spawn_position, Death, Knockback, SpawnTelegraph, TelegraphAssets, MAX_ENEMIES, TELEGRAPH_TIME}, kinematics::Velocity, level::FLOOR_SIZE, navigation::NavGrid, player::{nearest_player, Player}, AppState, RunState};
+
+/// Seconds between formations the director schedules on its own.
+const FORMATION_INTERVAL: f32 = 25.;
+const FIRST_FORMATION: f32 = 15.;
+/// Rings and lines start this far past the edge of the camera view.
+const OFFSCREEN_MARGIN: f32 = 60.;
+const RING_SIZE: usize = 12;
+const RING_MIN_RADIUS: f32 = 120.;
+const RING_CLOSE_SPEED: f32 = 40.;
+const LINE_SIZE: usize = 10;
+const LINE_SPACING: f32 = 60.;
+const LINE_SPEED: f32 = 25.;
+const LINE_LIFETIME: f32 = 60.;
+const VEE_FOLLOWERS: usize = 6;
+const VEE_SPACING: f32 = 45.;
+const SWARM_SIZE: usize = 20;
+const SWARM_RADIUS: f32 = 80.;
+const SCATTER_IMPULSE: f32 = 200.;
+
+/// Position, archetype and optional (slot, leader) for a member about to spawn.
+type MemberPlan = (Vec2, Archetype, Option<(Vec2, bool)>);
+
+pub struct FormationPlugin;
+
+impl Plugin for FormationPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<WaveDirector>()
+            .add_systems(OnEnter(AppState::InGame), reset_wave_director)
+            .add_systems(OnExit(AppState::InGame), despawn_formations)
+            .add_systems(Update, (
+                direct_waves,
+                update_formations.before(move_enemies),
+            ).run_if(in_state(RunState::Running)))
+        ;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FormationKind {
+    /// Ring around the player that slowly tightens.
+    Ring,
+    /// Row of enemies sweeping across the arena.
+    Line,
+    /// Leader chasing the player with followers in a V behind it.
+    Vee,
+    /// Dense cluster of chasers that acts on its own.
+    Swarm,
+}
+
+const FORMATION_KINDS: [FormationKind; 4] = [FormationKind::Ring, FormationKind::Line, FormationKind::Vee, FormationKind::Swarm];
+
+/// Schedules formations on top of the regular trickle of single spawns.
+#[derive(Resource)]
+pub struct WaveDirector {
+    elapsed: f32,
+    next_formation: f32,
+    queue: Vec<(f32, FormationKind)>,
+}
+
+impl Default for WaveDirector {
+    fn default() -> Self {
+        Self {
+            elapsed: 0.,
+            next_formation: FIRST_FORMATION,
+            queue: Vec::new(),
+        }
+    }
+}
+
+impl WaveDirector {
+    /// Spawns `kind` once the run has lasted `at` seconds.
+    pub fn schedule(&mut self, kind: FormationKind, at: f32) {
+        self.queue.push((at, kind));
+    }
+}
+
+/// Shared state of one formation, referenced by its members.
+#[derive(Component)]
+struct Formation {
+    kind: FormationKind,
+    spawn_time: f32,
+    origin: Vec2,
+    direction: Vec2,
+    /// Radius a ring starts closing in from.
+    radius: f32,
+    had_leader: bool,
+}
+
+impl Formation {
+    fn ring_radius(&self, age: f32) -> f32 {
+        (self.radius - RING_CLOSE_SPEED * (age - TELEGRAPH_TIME).max(0.)).max(RING_MIN_RADIUS)
+    }
+}
+
+/// What a wave is placed around: the players, the camera view and the walkable floor.
+#[derive(SystemParam)]
+struct SpawnArea<'w, 's> {
+    players: Query<'w, 's, &'static Transform, With<Player>>,
+    camera: Query<'w, 's, (&'static Transform, &'static OrthographicProjection), With<Camera2d>>,
+    nav_grid: Res<'w, NavGrid>,
+}
+
+#[derive(Component, Debug, Clone, Copy)]
+pub struct FormationMember {
+    formation: Entity,
+    /// Offset of the slot, in the frame the formation kind uses.
+    slot: Vec2,
+    leader: bool,
+    /// Where the member should head this frame, `None` to follow its own behaviour.
+    pub target: Option<Vec2>,
+}
+
+fn reset_wave_director(mut director: ResMut<WaveDirector>) {
+    *director = WaveDirector::default();
+}
+
+fn direct_waves(
+    mut commands: Commands,
+    mut director: ResMut<WaveDirector>,
+    enemies_query: Query<(), CappedEnemy>,
+    area: SpawnArea,
+    telegraph_assets: Res<TelegraphAssets>,
+    time: Res<Time>,
+) {
+    director.elapsed += time.delta_seconds();
+    let mut rng = rand::thread_rng();
+    if director.elapsed >= director.next_formation {
+        director.next_formation += FORMATION_INTERVAL;
+        let (elapsed, kind) = (director.elapsed, *FORMATION_KINDS.choose(&mut rng).unwrap());
+        director.schedule(kind, elapsed);
+    }
+
+    let elapsed = director.elapsed;
+    let due: Vec<_> = director.queue.iter().filter(|(at, _)| *at <= elapsed).map(|&(_, kind)| kind).collect();
+    if due.is_empty() {
+        return;
+    }
+    director.queue.retain(|(at, _)| *at > elapsed);
+
+    let players: Vec<_> = area.players.iter().map(|transform| transform.translation.truncate()).collect();
+    if players.is_empty() {
+        return;
+    }
+    let Ok((camera_transform, projection)) = area.camera.get_single() else {
+        return;
+    };
+    let view = Rect::from_center_size(
+        camera_transform.translation.truncate() + projection.area.center(),
+        projection.area.size(),
+    );
+    let arena = Rect::from_center_size(Vec2::ZERO, Vec2::splat(FLOOR_SIZE));
+    let spawn_time = time.elapsed_seconds() + TELEGRAPH_TIME;
+    let mut alive = enemies_query.iter().count();
+
+    for kind in due {
+        // Each wave goes after one player picked at random.
+        let player_position = *players.choose(&mut rng).unwrap();
+        // Anything at least this far from the player is outside the view.
+        let offscreen = player_position.distance(view.center()) + view.half_size().length() + OFFSCREEN_MARGIN;
+        let (origin, direction, members): (Vec2, Vec2, Vec<MemberPlan>) = match kind {
+            FormationKind::Ring => {
+                let members = (0..RING_SIZE)
+                    .map(|index| {
+                        let slot = Vec2::from_angle(index as f32 * TAU / RING_SIZE as f32);
+                        (player_position + slot * offscreen, Archetype::Chaser, Some((slot, false)))
+                    })
+                    .collect();
+                (player_position, Vec2::ZERO, members)
+            }
+            FormationKind::Line => {
+                let direction = Vec2::from_angle(rng.gen_range(0.0..TAU));
+                let origin = player_position - direction * offscreen;
+                let members = (0..LINE_SIZE)
+                    .map(|index| {
+                        let slot = direction.perp() * (index as f32 - (LINE_SIZE - 1) as f32 / 2.) * LINE_SPACING;
+                        (origin + slot, Archetype::Chaser, Some((slot, false)))
+                    })
+                    .collect();
+                (origin, direction, members)
+            }
+            FormationKind::Vee => {
+                let Some(origin) = spawn_position(view, &players, &area.nav_grid, &mut rng) else {
+                    continue;
+                };
+                let forward = (player_position - origin).normalize_or_zero();
+                let mut members = vec![(origin, Archetype::Flanker, Some((Vec2::ZERO, true)))];
+                for index in 0..VEE_FOLLOWERS {
+                    let rank = (index / 2 + 1) as f32;
+                    let side = if index % 2 == 0 { 1. } else { -1. };
+                    // Slots are expressed with x along the leader's heading and y to its left.
+                    let slot = Vec2::new(-rank, side * rank) * VEE_SPACING;
+                    members.push((origin + forward.rotate(slot), Archetype::Chaser, Some((slot, false))));
+                }
+                (origin, forward, members)
+            }
+            FormationKind::Swarm => {
+                let Some(origin) = spawn_position(view, &players, &area.nav_grid, &mut rng) else {
+                    continue;
+                };
+                let members = (0..SWARM_SIZE)
+                    .map(|_| {
+                        let offset = Vec2::from_angle(rng.gen_range(0.0..TAU)) * rng.gen_range(0.0..SWARM_RADIUS);
+                        (origin + offset, Archetype::Chaser, None)
+                    })
+                    .collect();
+                (origin, Vec2::ZERO, members)
+            }
+        };
+
+        if alive + members.len() > MAX_ENEMIES {
+            continue;
+        }
+
+        // Swarms break up straight away, so they need no shared formation state.
+        let formation = (kind != FormationKind::Swarm).then(|| commands.spawn(Formation {
+            kind,
+            spawn_time: time.elapsed_seconds(),
+            origin,
+            direction,
+            radius: offscreen,
+            had_leader: false,
+        }).id());
+
+        for (position, archetype, slot) in members {
+            if !arena.contains(position) || !area.nav_grid.cell(position).is_some_and(|cell| area.nav_grid.is_walkable(cell)) {
+                continue;
+            }
+            let mut telegraph = SpawnTelegraph::new(archetype, spawn_time);
+            telegraph.formation = formation.zip(slot).map(|(formation, (slot, leader))| FormationMember { formation, slot, leader, target: None });
+            commands.spawn((telegraph_assets.bundle(position), telegraph));
+            alive += 1;
+        }
+    }
+}
+
+fn update_formations(
+    mut commands: Commands,
+    mut formations_query: Query<(Entity, &mut Formation)>,
+    mut members_query: Query<(Entity, &Transform, &mut FormationMember, &mut Knockback, &mut Velocity), Without<Death>>,
+    telegraphs_query: Query<&SpawnTelegraph>,
+    player_query: Query<(Entity, &Transform), With<Player>>,
+    time: Res<Time>,
+) {
+    let players: Vec<_> = player_query.iter().map(|(entity, transform)| (entity, transform.translation.truncate())).collect();
+
+    let mut leaders = HashMap::new();
+    let mut centers: HashMap<Entity, (Vec2, f32)> = HashMap::new();
+    for (_, transform, member, _, _) in &members_query {
+        let position = transform.translation.truncate();
+        if member.leader {
+            leaders.insert(member.formation, position);
+        }
+        let center = centers.entry(member.formation).or_default();
+        center.0 += position;
+        center.1 += 1.;
+    }
+
+    // Formations that have stopped steering their members, and whether they scatter.
+    let mut dissolved = HashMap::new();
+    for (entity, mut formation) in &mut formations_query {
+        let age = time.elapsed_seconds() - formation.spawn_time;
+        let pending = telegraphs_query.iter().any(|telegraph| telegraph.formation.is_some_and(|member| member.formation == entity));
+        if !centers.contains_key(&entity) && !pending {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        match formation.kind {
+            FormationKind::Ring if formation.ring_radius(age) <= RING_MIN_RADIUS => {
+                dissolved.insert(entity, false);
+            }
+            FormationKind::Line if age > LINE_LIFETIME => {
+                dissolved.insert(entity, false);
+            }
+            FormationKind::Vee if leaders.contains_key(&entity) => formation.had_leader = true,
+            FormationKind::Vee if formation.had_leader => {
+                dissolved.insert(entity, true);
+            }
+            _ => {}
+        }
+    }
+
+    for (member_entity, transform, mut member, mut knockback, mut velocity) in &mut members_query {
+        let Ok((_, formation)) = formations_query.get(member.formation) else {
+            commands.entity(member_entity).remove::<FormationMember>();
+            continue;
+        };
+
+        if let Some(&scatter) = dissolved.get(&member.formation) {
+            if scatter {
+                let (sum, count) = centers[&member.formation];
+                let away = (transform.translation.truncate() - sum / count).normalize_or(Vec2::from_angle(rand::thread_rng().gen_range(0.0..TAU)));
+                knockback.push(&mut velocity, away * SCATTER_IMPULSE, time.elapsed_seconds());
+            }
+            commands.entity(member_entity).remove::<FormationMember>();
+            continue;
+        }
+
+        let age = time.elapsed_seconds() - formation.spawn_time;
+        member.target = match formation.kind {
+            // Rings close in on whoever is nearest to where they formed.
+            FormationKind::Ring => nearest_player(&players, formation.origin).map(|(_, player_position)| player_position + member.slot * formation.ring_radius(age)),
+            FormationKind::Line => Some(formation.origin + formation.direction * LINE_SPEED * age + member.slot),
+            FormationKind::Vee if member.leader => None,
+            FormationKind::Vee => leaders.get(&member.formation).map(|&leader| {
+                let forward = nearest_player(&players, leader).map_or(formation.direction, |(_, player_position)| (player_position - leader).normalize_or(formation.direction));
+                leader + forward.rotate(member.slot)
+            }),
+            FormationKind::Swarm => None,
+        };
+    }
+}
+
+fn despawn_formations(
+    mut commands: Commands,
+    formations_query: Query<Entity, With<Formation>>,
+) {
+    for entity in &formations_query {
+        commands.entity(entity).despawn();
+    }
+}
@@ -1,14 +1,14 @@
 use bevy:: prelude::*;
 
-use crate::{enemies::EnemyKilledEvent, player::{PlayerHitEvent, PLAYER_LIFES}, AppState};
+use crate::{boss::Boss, character::Character, dash::Dash, enemies::{Death, EnemyKilledEvent, Health}, loot::Collected, player::{spawn_player, Player}, upgrade::Experience, AppState};
 
 pub struct GamePlugin;
 
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
         app
-            .add_systems(OnEnter(AppState::InGame), setup_ui)
-            .add_systems(Update, (update_score, update_health_ui).run_if(in_state(AppState::InGame)))
+            .add_systems(OnEnter(AppState::InGame), setup_ui.after(spawn_player))
+            .add_systems(Update, (update_score, update_health_ui, update_boss_health_bar, update_dash_meter, update_experience_bar, update_coins).run_if(in_state(AppState::InGame)))
             .add_systems(OnExit(AppState::InGame), cleanup_menu)
         ;
     }
@@ -19,15 +19,43 @@ struct MenuData {
     score_entity: Entity,
 }
 
+/// Ties a HUD widget to the player with this index.
+#[derive(Component, Clone, Copy)]
+struct PlayerPanel(usize);
+
 #[derive(Component, Default)]
 struct Score(pub i32);
 
 #[derive(Component, Default)]
-struct Health_UI(pub i8);
+struct Health_UI(pub i32);
+
+#[derive(Component)]
+struct BossHealthBar;
+
+#[derive(Component)]
+struct BossHealthBarFill;
+
+#[derive(Component)]
+struct DashCharges;
+
+#[derive(Component)]
+struct DashMeterFill;
+
+#[derive(Component)]
+struct LevelText;
+
+#[derive(Component)]
+struct ExperienceBarFill;
+
+#[derive(Component)]
+struct CoinsText;
 
 fn setup_ui(
-    mut commands: Commands
+    mut commands: Commands,
+    player_query: Query<(&Player, &Health, &Character)>,
 ) {
+    let mut players: Vec<_> = player_query.iter().collect();
+    players.sort_by_key(|(player, ..)| player.index);
     let score_entity = commands
     .spawn(NodeBundle {
         style: Style {
@@ -41,36 +69,183 @@ fn setup_ui(
         ..default()
     })
     .with_children(|parent| {
-        parent
-            .spawn((TextBundle {
-                text: Text::from_section("0", TextStyle {
-                    font_size: 40.0,
-                    color: Color::srgb(0.9, 0.9, 0.9),
-                    ..default()
-                }), 
+            parent
+            .spawn((NodeBundle {
                 style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(20.),
+                    left: Val::Percent(25.),
                     width: Val::Percent(50.),
-                    height: Val::Px(65.),
+                    height: Val::Px(20.),
+                    padding: UiRect::all(Val::Px(3.)),
                     ..default()
                 },
+                background_color: Color::srgb(0.1, 0.1, 0.1).into(),
+                visibility: Visibility::Hidden,
                 ..default()
-            }, Score::default()));
+            }, BossHealthBar))
+            .with_children(|parent| {
+                parent.spawn((NodeBundle {
+                    style: Style {
+                        width: Val::Percent(100.),
+                        height: Val::Percent(100.),
+                        ..default()
+                    },
+                    background_color: Color::srgb(0.8, 0.1, 0.1).into(),
+                    ..default()
+                }, BossHealthBarFill));
+            });
 
+            // One panel per player along the bottom, in join order.
             parent
-            .spawn((TextBundle {
-                text: Text::from_section(PLAYER_LIFES.to_string(), TextStyle {
-                    font_size: 40.0,
+            .spawn(NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Px(20.),
+                    left: Val::Px(20.),
+                    column_gap: Val::Px(30.),
+                    ..default()
+                },
+                ..default()
+            })
+            .with_children(|parent| {
+                for &(player, health, character) in &players {
+                    let panel = PlayerPanel(player.index);
+                    parent
+                    .spawn(NodeBundle {
+                        style: Style {
+                            flex_direction: FlexDirection::Column,
+                            row_gap: Val::Px(4.),
+                            ..default()
+                        },
+                        ..default()
+                    })
+                    .with_children(|parent| {
+                        parent.spawn(TextBundle::from_section(format!("P{} {}", player.index + 1, character.name()), TextStyle {
+                            font_size: 20.0,
+                            color: character.color(),
+                            ..default()
+                        }));
+
+                        parent
+                        .spawn(NodeBundle {
+                            style: Style {
+                                column_gap: Val::Px(16.),
+                                ..default()
+                            },
+                            ..default()
+                        })
+                        .with_children(|parent| {
+                            parent.spawn((TextBundle::from_section("0", TextStyle {
+                                font_size: 32.0,
+                                color: Color::srgb(0.9, 0.9, 0.9),
+                                ..default()
+                            }), Score::default(), panel));
+
+                            parent.spawn((TextBundle::from_section(format!("HP {}", health.current()), TextStyle {
+                                font_size: 32.0,
+                                color: Color::srgb(0.9, 0.9, 0.9),
+                                ..default()
+                            }), Health_UI(health.current()), panel));
+                        });
+
+                        parent
+                        .spawn(NodeBundle {
+                            style: Style {
+                                align_items: AlignItems::Center,
+                                column_gap: Val::Px(10.),
+                                ..default()
+                            },
+                            ..default()
+                        })
+                        .with_children(|parent| {
+                            parent.spawn((TextBundle::from_section("DASH", TextStyle {
+                                font_size: 20.0,
+                                color: Color::srgb(0.9, 0.9, 0.9),
+                                ..default()
+                            }), DashCharges, panel));
+
+                            parent
+                            .spawn(NodeBundle {
+                                style: Style {
+                                    width: Val::Px(100.),
+                                    height: Val::Px(10.),
+                                    padding: UiRect::all(Val::Px(2.)),
+                                    ..default()
+                                },
+                                background_color: Color::srgb(0.1, 0.1, 0.1).into(),
+                                ..default()
+                            })
+                            .with_children(|parent| {
+                                parent.spawn((NodeBundle {
+                                    style: Style {
+                                        width: Val::Percent(100.),
+                                        height: Val::Percent(100.),
+                                        ..default()
+                                    },
+                                    background_color: Color::srgb(0.3, 0.7, 0.9).into(),
+                                    ..default()
+                                }, DashMeterFill, panel));
+                            });
+                        });
+                    });
+                }
+            });
+
+            parent.spawn((TextBundle {
+                text: Text::from_section("LV 1", TextStyle {
+                    font_size: 24.0,
                     color: Color::srgb(0.9, 0.9, 0.9),
-            
                     ..default()
-                }), 
+                }),
                 style: Style {
-                    width: Val::Px(100.),
-                    height: Val::Px(65.),
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Px(20.),
+                    right: Val::Px(20.),
                     ..default()
                 },
                 ..default()
-            }, Health_UI(PLAYER_LIFES)));
+            }, LevelText));
+
+            parent.spawn((TextBundle {
+                text: Text::from_section("COINS 0", TextStyle {
+                    font_size: 24.0,
+                    color: Color::srgb(1., 0.84, 0.),
+                    ..default()
+                }),
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(20.),
+                    right: Val::Px(20.),
+                    ..default()
+                },
+                ..default()
+            }, CoinsText));
+
+            parent
+            .spawn(NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Px(0.),
+                    left: Val::Px(0.),
+                    width: Val::Percent(100.),
+                    height: Val::Px(8.),
+                    ..default()
+                },
+                background_color: Color::srgb(0.1, 0.1, 0.1).into(),
+                ..default()
+            })
+            .with_children(|parent| {
+                parent.spawn((NodeBundle {
+                    style: Style {
+                        width: Val::Percent(0.),
+                        height: Val::Percent(100.),
+                        ..default()
+                    },
+                    background_color: Color::srgb(0.3, 0.9, 0.3).into(),
+                    ..default()
+                }, ExperienceBarFill));
+            });
     })
     .id();
 commands.insert_resource(MenuData { score_entity });
@@ -79,29 +254,105 @@ commands.insert_resource(MenuData { score_entity });
 
 fn update_score(
     mut events: EventReader<EnemyKilledEvent>,
-    mut text_query: Query<(&mut Text, &mut Score)>
+    player_query: Query<&Player>,
+    mut text_query: Query<(&mut Text, &mut Score, &PlayerPanel)>
+) {
+    for event in events.read() {
+        let Some(player) = event.killer.and_then(|killer| player_query.get(killer).ok()) else {
+            continue;
+        };
+        for (mut text, mut score, panel) in &mut text_query {
+            if panel.0 == player.index {
+                score.0 += event.points;
+                text.sections[0].value = score.0.to_string();
+            }
+        }
+    }
+}
+
+fn update_health_ui(
+    player_query: Query<(&Player, &Health), Changed<Health>>,
+    mut text_query: Query<(&mut Text, &mut Health_UI, &PlayerPanel)>,
 ) {
-    let Ok((mut text, mut score)) = text_query.get_single_mut() else {
+    // Follows each player's health directly, so pickups that heal show up too.
+    for (player, player_health) in &player_query {
+        for (mut text, mut health_ui, panel) in &mut text_query {
+            if panel.0 != player.index {
+                continue;
+            }
+            health_ui.0 = player_health.current();
+            text.sections[0].value = if player_health.is_dead() { "DOWN".to_string() } else { format!("HP {}", health_ui.0) };
+        }
+    }
+}
+
+fn update_boss_health_bar(
+    boss_query: Query<&Health, (With<Boss>, Without<Death>)>,
+    mut bar_query: Query<&mut Visibility, With<BossHealthBar>>,
+    mut fill_query: Query<&mut Style, With<BossHealthBarFill>>,
+) {
+    let Ok(mut bar_visibility) = bar_query.get_single_mut() else {
+        return;
+    };
+
+    let Some(health) = boss_query.iter().next() else {
+        *bar_visibility = Visibility::Hidden;
         return;
     };
 
-    for _ in events.read() {
-        score.0 += 1;
-        text.sections[0].value = score.0.to_string();
+    *bar_visibility = Visibility::Inherited;
+    if let Ok(mut fill_style) = fill_query.get_single_mut() {
+        let fraction = health.fraction();
+        fill_style.width = Val::Percent(fraction * 100.);
     }
 }
 
-fn update_health_ui(
-    mut events: EventReader<PlayerHitEvent>,
-    mut text_query: Query<(&mut Text, &mut Health_UI)>,
+fn update_dash_meter(
+    player_query: Query<(&Player, &Dash), Changed<Dash>>,
+    mut text_query: Query<(&mut Text, &PlayerPanel), With<DashCharges>>,
+    mut fill_query: Query<(&mut Style, &PlayerPanel), With<DashMeterFill>>,
+) {
+    for (player, dash) in &player_query {
+        for (mut text, panel) in &mut text_query {
+            if panel.0 == player.index {
+                text.sections[0].value = format!("DASH {}", dash.charges());
+            }
+        }
+        for (mut fill_style, panel) in &mut fill_query {
+            if panel.0 == player.index {
+                fill_style.width = Val::Percent(dash.recharge_fraction() * 100.);
+            }
+        }
+    }
+}
+
+fn update_experience_bar(
+    experience: Res<Experience>,
+    mut text_query: Query<&mut Text, With<LevelText>>,
+    mut fill_query: Query<&mut Style, With<ExperienceBarFill>>,
 ) {
-    let Ok((mut text, mut health_ui)) = text_query.get_single_mut() else {
+    if !experience.is_changed() {
         return;
-    };
+    }
+
+    if let Ok(mut text) = text_query.get_single_mut() {
+        text.sections[0].value = format!("LV {}", experience.level);
+    }
+    if let Ok(mut fill_style) = fill_query.get_single_mut() {
+        fill_style.width = Val::Percent(experience.xp as f32 / experience.to_next() as f32 * 100.);
+    }
+}
+
+fn update_coins(
+    collected: Res<Collected>,
+    mut text_query: Query<&mut Text, With<CoinsText>>,
+) {
+    if !collected.is_changed() {
+        return;
+    }
 
-    for _ in events.read() {
-        health_ui.0 -= 1;
-        text.sections[0].value = health_ui.0.to_string();
+    if let Ok(mut text) = text_query.get_single_mut() {
+        text.sections[0].value = format!("COINS {}", collected.coins);
     }
 }
 
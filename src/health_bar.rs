@@ -0,0 +1,177 @@
+use bevy::{prelude::*, sprite::Anchor};
+
+use crate::{boss::Boss, enemies::{Death, Health}, player::Player, AppState, RunState};
+
+const BAR_SIZE: Vec2 = Vec2::new(40., 5.);
+const BAR_OFFSET: Vec3 = Vec3::new(0., 32., 1.);
+const FADE_DELAY: f32 = 3.;
+const FADE_TIME: f32 = 0.5;
+const VIEW_MARGIN: f32 = 50.;
+
+/// Health changes that should raise a floating bar; players and bosses have their own UI.
+type Damaged = (Changed<Health>, Without<Player>, Without<Boss>);
+type BarItem = (Entity, &'static mut HealthBar, &'static Children, &'static mut Transform, &'static mut Sprite, &'static mut Visibility);
+type BarCamera = (With<Camera2d>, Without<HealthBar>, Without<HealthTracker>);
+
+pub struct HealthBarPlugin;
+
+impl Plugin for HealthBarPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<HealthBarPool>()
+            .add_systems(Update, (track_damage, update_health_bars).chain().run_if(in_state(RunState::Running)))
+            .add_systems(OnExit(AppState::InGame), release_health_bars)
+        ;
+    }
+}
+
+/// Remembers when an entity was last hit and which bar it is using.
+#[derive(Component)]
+pub struct HealthTracker {
+    last_hit: f32,
+    bar: Option<Entity>,
+}
+
+#[derive(Component)]
+struct HealthBar {
+    owner: Option<Entity>,
+}
+
+#[derive(Component)]
+struct HealthBarFill;
+
+/// Bars whose owner died or healed up, kept around for reuse.
+#[derive(Resource, Default)]
+struct HealthBarPool(Vec<Entity>);
+
+fn spawn_health_bar(commands: &mut Commands, owner: Entity) -> Entity {
+    let fill = commands.spawn((SpriteBundle {
+        sprite: Sprite {
+            color: Color::srgb(0.2, 0.9, 0.2),
+            custom_size: Some(BAR_SIZE),
+            anchor: Anchor::CenterLeft,
+            ..default()
+        },
+        transform: Transform::from_xyz(-BAR_SIZE.x / 2., 0., 0.1),
+        ..default()
+    }, HealthBarFill)).id();
+
+    commands.spawn((SpriteBundle {
+        sprite: Sprite {
+            color: Color::srgb(0.1, 0.1, 0.1),
+            custom_size: Some(BAR_SIZE + 2.),
+            ..default()
+        },
+        visibility: Visibility::Hidden,
+        ..default()
+    }, HealthBar { owner: Some(owner) }))
+    .add_child(fill)
+    .id()
+}
+
+fn track_damage(
+    mut commands: Commands,
+    mut pool: ResMut<HealthBarPool>,
+    mut health_query: Query<(Entity, &Health, Option<&mut HealthTracker>), Damaged>,
+    mut bars_query: Query<&mut HealthBar>,
+    time: Res<Time>,
+) {
+    for (entity, health, tracker) in &mut health_query {
+        let Some(mut tracker) = tracker else {
+            commands.entity(entity).insert(HealthTracker {
+                last_hit: f32::NEG_INFINITY,
+                bar: None,
+            });
+            continue;
+        };
+
+        if health.current() >= health.max() {
+            continue;
+        }
+        tracker.last_hit = time.elapsed_seconds();
+        if tracker.bar.is_some() {
+            continue;
+        }
+
+        let pooled = pool.0.pop().and_then(|bar_entity| bars_query.get_mut(bar_entity).ok().map(|bar| (bar_entity, bar)));
+        let bar_entity = match pooled {
+            Some((bar_entity, mut bar)) => {
+                bar.owner = Some(entity);
+                bar_entity
+            }
+            None => spawn_health_bar(&mut commands, entity),
+        };
+        tracker.bar = Some(bar_entity);
+    }
+}
+
+fn update_health_bars(
+    mut pool: ResMut<HealthBarPool>,
+    mut owners_query: Query<(&Transform, &Health, &mut HealthTracker, Has<Death>), Without<HealthBar>>,
+    mut bars_query: Query<BarItem, Without<HealthTracker>>,
+    mut fills_query: Query<&mut Sprite, (With<HealthBarFill>, Without<HealthBar>)>,
+    camera_query: Query<(&Transform, &OrthographicProjection), BarCamera>,
+    time: Res<Time>,
+) {
+    let Ok((camera_transform, projection)) = camera_query.get_single() else {
+        return;
+    };
+    let view = Rect::from_center_size(
+        camera_transform.translation.truncate() + projection.area.center(),
+        projection.area.size() + VIEW_MARGIN * 2.,
+    );
+
+    for (bar_entity, mut bar, children, mut bar_transform, mut bar_sprite, mut visibility) in &mut bars_query {
+        let Some(owner) = bar.owner else {
+            continue;
+        };
+
+        let released = match owners_query.get_mut(owner) {
+            Ok((owner_transform, health, mut tracker, is_dead)) => {
+                let fade = ((time.elapsed_seconds() - tracker.last_hit - FADE_DELAY) / FADE_TIME).clamp(0., 1.);
+                if is_dead || fade >= 1. {
+                    tracker.bar = None;
+                    true
+                } else {
+                    bar_transform.translation = owner_transform.translation + BAR_OFFSET;
+                    *visibility = if view.contains(bar_transform.translation.truncate()) {
+                        Visibility::Visible
+                    } else {
+                        Visibility::Hidden
+                    };
+
+                    let alpha = 1. - fade;
+                    bar_sprite.color.set_alpha(alpha);
+                    for &fill in children {
+                        if let Ok(mut fill_sprite) = fills_query.get_mut(fill) {
+                            let fraction = health.fraction();
+                            fill_sprite.custom_size = Some(Vec2::new(BAR_SIZE.x * fraction, BAR_SIZE.y));
+                            fill_sprite.color.set_alpha(alpha);
+                        }
+                    }
+                    false
+                }
+            }
+            Err(_) => true,
+        };
+
+        if released {
+            bar.owner = None;
+            *visibility = Visibility::Hidden;
+            pool.0.push(bar_entity);
+        }
+    }
+}
+
+/// Hides the bars still in use when the run ends and hands them back to the pool.
+fn release_health_bars(
+    mut pool: ResMut<HealthBarPool>,
+    mut bars_query: Query<(Entity, &mut HealthBar, &mut Visibility)>,
+) {
+    for (bar_entity, mut bar, mut visibility) in &mut bars_query {
+        if bar.owner.take().is_some() {
+            *visibility = Visibility::Hidden;
+            pool.0.push(bar_entity);
+        }
+    }
+}
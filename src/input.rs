@@ -0,0 +1,511 @@
+use std::fs;
+
+use bevy::{input::{gamepad::{GamepadConnection, GamepadConnectionEvent}, InputSystem}, prelude::*, utils::HashMap};
+use serde::{Deserialize, Serialize};
+
+use crate::{AppState, RunState};
+
+/// Bindings file, read at startup and rewritten whenever a binding is changed in game.
+const BINDINGS_PATH: &str = "bindings.ron";
+/// Stick deflection below which input is ignored, to hide drift.
+const STICK_DEADZONE: f32 = 0.2;
+
+pub struct InputPlugin;
+
+impl Plugin for InputPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<ButtonInput<InputAction>>()
+            .init_resource::<DeviceInputs>()
+            .init_resource::<Rebinding>()
+            .insert_resource(Bindings::load())
+            .add_systems(PreUpdate, update_actions.after(InputSystem))
+            .add_systems(Update, handle_gamepad_connections)
+            .add_systems(Update, (
+                // Goes first so a key being bound does not also pause or unpause.
+                rebind_keys,
+                // The upgrade choice keeps the game paused until something is picked.
+                toggle_pause.run_if(in_state(RunState::Running).or_else(in_state(RunState::Paused))),
+            ).chain().run_if(in_state(AppState::InGame)))
+            .add_systems(OnEnter(RunState::Paused), pause_time)
+            .add_systems(OnExit(RunState::Paused), resume_time)
+        ;
+    }
+}
+
+const ACTIONS: [InputAction; 10] = [
+    InputAction::MoveUp,
+    InputAction::MoveDown,
+    InputAction::MoveLeft,
+    InputAction::MoveRight,
+    InputAction::Jump,
+    InputAction::Fire,
+    InputAction::Pause,
+    InputAction::Confirm,
+    InputAction::Dash,
+    InputAction::Quit,
+];
+/// While paused, these pick the action in `ACTIONS` that the next key or button press is bound to.
+/// Actions added later wrap around to F1 so the earlier keys keep their meaning.
+const REBIND_KEYS: [KeyCode; 10] = [KeyCode::F5, KeyCode::F6, KeyCode::F7, KeyCode::F8, KeyCode::F9, KeyCode::F10, KeyCode::F11, KeyCode::F12, KeyCode::F1, KeyCode::F2];
+
+/// What the player wants to do, independent of the key that asked for it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum InputAction {
+    MoveUp,
+    MoveDown,
+    MoveLeft,
+    MoveRight,
+    Jump,
+    Fire,
+    Pause,
+    /// Presses the menu button.
+    Confirm,
+    Dash,
+    /// Leaves the game from the death screen.
+    Quit,
+}
+
+/// Keys and gamepad buttons bound to each action, any of them triggers it.
+#[derive(Resource, Debug, Clone, Serialize, Deserialize)]
+pub struct Bindings {
+    keys: HashMap<InputAction, Vec<KeyCode>>,
+    #[serde(default)]
+    buttons: HashMap<InputAction, Vec<GamepadButtonType>>,
+}
+
+impl Default for Bindings {
+    fn default() -> Self {
+        Self {
+            keys: HashMap::from([
+                (InputAction::MoveUp, vec![KeyCode::KeyW, KeyCode::ArrowUp]),
+                (InputAction::MoveDown, vec![KeyCode::KeyS, KeyCode::ArrowDown]),
+                (InputAction::MoveLeft, vec![KeyCode::KeyA, KeyCode::ArrowLeft]),
+                (InputAction::MoveRight, vec![KeyCode::KeyD, KeyCode::ArrowRight]),
+                (InputAction::Jump, vec![KeyCode::Space]),
+                (InputAction::Fire, vec![KeyCode::KeyJ]),
+                (InputAction::Pause, vec![KeyCode::Escape, KeyCode::KeyP]),
+                (InputAction::Confirm, vec![KeyCode::Enter]),
+                (InputAction::Dash, vec![KeyCode::ShiftLeft, KeyCode::KeyK]),
+                (InputAction::Quit, vec![KeyCode::KeyQ]),
+            ]),
+            buttons: HashMap::from([
+                (InputAction::MoveUp, vec![GamepadButtonType::DPadUp]),
+                (InputAction::MoveDown, vec![GamepadButtonType::DPadDown]),
+                (InputAction::MoveLeft, vec![GamepadButtonType::DPadLeft]),
+                (InputAction::MoveRight, vec![GamepadButtonType::DPadRight]),
+                (InputAction::Jump, vec![GamepadButtonType::South]),
+                (InputAction::Fire, vec![GamepadButtonType::RightTrigger, GamepadButtonType::RightTrigger2]),
+                (InputAction::Pause, vec![GamepadButtonType::Start]),
+                (InputAction::Confirm, vec![GamepadButtonType::South, GamepadButtonType::Start]),
+                (InputAction::Dash, vec![GamepadButtonType::East, GamepadButtonType::LeftTrigger]),
+                (InputAction::Quit, vec![GamepadButtonType::Select]),
+            ]),
+        }
+    }
+}
+
+impl Bindings {
+    /// Reads the bindings file, falling back to the defaults if it is missing or invalid.
+    fn load() -> Self {
+        let Ok(contents) = fs::read_to_string(BINDINGS_PATH) else {
+            return Self::default();
+        };
+        match ron::from_str::<Self>(&contents) {
+            Ok(mut bindings) => {
+                // Actions added since the file was written keep their default bindings.
+                let defaults = Self::default();
+                for (action, keys) in defaults.keys {
+                    bindings.keys.entry(action).or_insert(keys);
+                }
+                for (action, buttons) in defaults.buttons {
+                    bindings.buttons.entry(action).or_insert(buttons);
+                }
+                bindings
+            }
+            Err(error) => {
+                warn!("ignoring invalid {BINDINGS_PATH}: {error}");
+                Self::default()
+            }
+        }
+    }
+
+    /// Replaces every key bound to `action` with `key`, unbinding it from other actions.
+    pub fn rebind(&mut self, action: InputAction, key: KeyCode) {
+        for keys in self.keys.values_mut() {
+            keys.retain(|&bound| bound != key);
+        }
+        self.keys.insert(action, vec![key]);
+    }
+
+    /// Gamepad counterpart of [`Bindings::rebind`].
+    pub fn rebind_button(&mut self, action: InputAction, button: GamepadButtonType) {
+        for buttons in self.buttons.values_mut() {
+            buttons.retain(|&bound| bound != button);
+        }
+        self.buttons.insert(action, vec![button]);
+    }
+
+    fn keys(&self, action: InputAction) -> &[KeyCode] {
+        self.keys.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    fn buttons(&self, action: InputAction) -> &[GamepadButtonType] {
+        self.buttons.get(&action).map_or(&[], Vec::as_slice)
+    }
+
+    /// Writes the bindings file, keeping the game running if that fails.
+    fn save(&self) {
+        let contents = match ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default()) {
+            Ok(contents) => contents,
+            Err(error) => {
+                warn!("could not serialize bindings: {error}");
+                return;
+            }
+        };
+        if let Err(error) = fs::write(BINDINGS_PATH, contents) {
+            warn!("could not write {BINDINGS_PATH}: {error}");
+        }
+    }
+}
+
+/// Stick positions of one gamepad, after the deadzone and clamped to unit length.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct AnalogInput {
+    pub movement: Vec2,
+    pub aim: Vec2,
+}
+
+/// Something a player can play with; the keyboard counts as one device.
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum InputDevice {
+    Keyboard,
+    Gamepad(Gamepad),
+}
+
+/// Actions and sticks read from a single device.
+#[derive(Debug, Default)]
+pub struct DeviceInput {
+    pub actions: ButtonInput<InputAction>,
+    pub analog: AnalogInput,
+}
+
+/// Input of the keyboard and every connected gamepad, kept apart so each player reads only their own.
+#[derive(Resource, Debug, Default)]
+pub struct DeviceInputs {
+    devices: HashMap<InputDevice, DeviceInput>,
+    /// Handed out for unplugged devices, nothing is ever pressed on it.
+    idle: DeviceInput,
+}
+
+impl DeviceInputs {
+    pub fn get(&self, device: InputDevice) -> &DeviceInput {
+        self.devices.get(&device).unwrap_or(&self.idle)
+    }
+
+    /// Devices on which `action` was pressed this frame.
+    pub fn just_pressed(&self, action: InputAction) -> impl Iterator<Item = InputDevice> + '_ {
+        self.devices.iter()
+            .filter(move |(_, input)| input.actions.just_pressed(action))
+            .map(|(&device, _)| device)
+    }
+}
+
+/// Action waiting for a key to be bound to it.
+#[derive(Resource, Default)]
+struct Rebinding(Option<InputAction>);
+
+/// Radial deadzone that rescales the rest of the range so movement starts from zero.
+fn apply_deadzone(stick: Vec2) -> Vec2 {
+    let length = stick.length();
+    if length < STICK_DEADZONE {
+        return Vec2::ZERO;
+    }
+    stick / length * ((length - STICK_DEADZONE) / (1. - STICK_DEADZONE)).min(1.)
+}
+
+fn update_actions(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<ButtonInput<GamepadButton>>,
+    gamepad_axes: Res<Axis<GamepadAxis>>,
+    bindings: Res<Bindings>,
+    mut actions: ResMut<ButtonInput<InputAction>>,
+    mut inputs: ResMut<DeviceInputs>,
+) {
+    // Unplugged gamepads drop out, new ones start with nothing pressed.
+    inputs.devices.retain(|device, _| match device {
+        InputDevice::Keyboard => true,
+        InputDevice::Gamepad(gamepad) => gamepads.contains(*gamepad),
+    });
+    inputs.devices.entry(InputDevice::Keyboard).or_default();
+    for gamepad in gamepads.iter() {
+        inputs.devices.entry(InputDevice::Gamepad(gamepad)).or_default();
+    }
+
+    for (device, input) in inputs.devices.iter_mut() {
+        input.actions.clear();
+        for action in ACTIONS {
+            let pressed = match device {
+                InputDevice::Keyboard => keyboard_input.any_pressed(bindings.keys(action).iter().copied()),
+                InputDevice::Gamepad(gamepad) => {
+                    gamepad_buttons.any_pressed(bindings.buttons(action).iter().map(|&button| GamepadButton::new(*gamepad, button)))
+                }
+            };
+            if pressed {
+                input.actions.press(action);
+            } else {
+                input.actions.release(action);
+            }
+        }
+
+        input.analog = match device {
+            InputDevice::Keyboard => AnalogInput::default(),
+            InputDevice::Gamepad(gamepad) => {
+                let stick = |x, y| Vec2::new(
+                    gamepad_axes.get(GamepadAxis::new(*gamepad, x)).unwrap_or(0.),
+                    gamepad_axes.get(GamepadAxis::new(*gamepad, y)).unwrap_or(0.),
+                );
+                AnalogInput {
+                    movement: apply_deadzone(stick(GamepadAxisType::LeftStickX, GamepadAxisType::LeftStickY)),
+                    aim: apply_deadzone(stick(GamepadAxisType::RightStickX, GamepadAxisType::RightStickY)),
+                }
+            }
+        };
+    }
+
+    // Menus and pausing listen to every device at once.
+    actions.clear();
+    for action in ACTIONS {
+        if inputs.devices.values().any(|input| input.actions.pressed(action)) {
+            actions.press(action);
+        } else {
+            actions.release(action);
+        }
+    }
+}
+
+/// Logs controllers as they come and go, pausing a running game when one is unplugged.
+fn handle_gamepad_connections(
+    mut connection_events: EventReader<GamepadConnectionEvent>,
+    state: Option<Res<State<RunState>>>,
+    mut next_state: ResMut<NextState<RunState>>,
+) {
+    for event in connection_events.read() {
+        match &event.connection {
+            GamepadConnection::Connected(info) => info!("gamepad {} connected: {}", event.gamepad.id, info.name),
+            GamepadConnection::Disconnected => {
+                info!("gamepad {} disconnected", event.gamepad.id);
+                if state.as_ref().is_some_and(|state| *state.get() == RunState::Running) {
+                    next_state.set(RunState::Paused);
+                }
+            }
+        }
+    }
+}
+
+pub fn toggle_pause(
+    actions: Res<ButtonInput<InputAction>>,
+    state: Res<State<RunState>>,
+    mut next_state: ResMut<NextState<RunState>>,
+) {
+    if !actions.just_pressed(InputAction::Pause) {
+        return;
+    }
+
+    next_state.set(match state.get() {
+        RunState::Running => RunState::Paused,
+        _ => RunState::Running,
+    });
+}
+
+/// Stops the clock while a run is held, so timers and timestamps pick up where they left off.
+pub fn pause_time(mut time: ResMut<Time<Virtual>>) {
+    time.pause();
+}
+
+pub fn resume_time(mut time: ResMut<Time<Virtual>>) {
+    time.unpause();
+}
+
+fn rebind_keys(
+    keyboard_input: Res<ButtonInput<KeyCode>>,
+    gamepad_buttons: Res<ButtonInput<GamepadButton>>,
+    state: Res<State<RunState>>,
+    mut rebinding: ResMut<Rebinding>,
+    mut bindings: ResMut<Bindings>,
+    mut actions: ResMut<ButtonInput<InputAction>>,
+) {
+    if *state.get() != RunState::Paused {
+        rebinding.0 = None;
+        return;
+    }
+
+    let mut bound = false;
+    for key in keyboard_input.get_just_pressed() {
+        if let Some(index) = REBIND_KEYS.iter().position(|rebind| rebind == key) {
+            rebinding.0 = Some(ACTIONS[index]);
+            info!("press a key or button to bind to {:?}", ACTIONS[index]);
+        } else if let Some(action) = rebinding.0.take() {
+            bindings.rebind(action, *key);
+            info!("bound {key:?} to {action:?}");
+            bound = true;
+        }
+    }
+    for button in gamepad_buttons.get_just_pressed() {
+        if let Some(action) = rebinding.0.take() {
+            bindings.rebind_button(action, button.button_type);
+            info!("bound {:?} to {action:?}", button.button_type);
+            bound = true;
+        }
+    }
+
+    if bound {
+        bindings.save();
+        // The press went into the binding, so it must not also act this frame.
+        actions.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use bevy::{input::gamepad::{GamepadAxisChangedEvent, GamepadButtonChangedEvent, GamepadInfo}, state::app::StatesPlugin};
+
+    use crate::{bullet::{spawn_bullets, Arsenal, Bullet}, player::{Player, PlayerMoveEvent}, stats::Stats};
+
+    use super::*;
+
+    const PAD: Gamepad = Gamepad { id: 0 };
+
+    fn app() -> App {
+        let mut app = App::new();
+        app
+            .add_plugins((bevy::input::InputPlugin, StatesPlugin))
+            .insert_state(AppState::InGame)
+            .add_sub_state::<RunState>()
+            .init_resource::<Time>()
+            .init_resource::<Time<Virtual>>()
+            .init_resource::<ButtonInput<InputAction>>()
+            .init_resource::<DeviceInputs>()
+            .insert_resource(Bindings::default())
+            .add_systems(PreUpdate, update_actions.after(InputSystem))
+            .add_systems(Update, handle_gamepad_connections)
+            .add_systems(OnEnter(RunState::Paused), pause_time);
+        app
+    }
+
+    fn connect(app: &mut App) {
+        app.world_mut().send_event(GamepadConnectionEvent::new(PAD, GamepadConnection::Connected(GamepadInfo { name: "test pad".to_string() })));
+        app.update();
+    }
+
+    fn tilt(app: &mut App, axis_type: GamepadAxisType, value: f32) {
+        app.world_mut().send_event(GamepadAxisChangedEvent::new(PAD, axis_type, value));
+    }
+
+    fn press(app: &mut App, button_type: GamepadButtonType, value: f32) {
+        app.world_mut().send_event(GamepadButtonChangedEvent::new(PAD, button_type, value));
+    }
+
+    fn analog(app: &App) -> AnalogInput {
+        app.world().resource::<DeviceInputs>().get(InputDevice::Gamepad(PAD)).analog
+    }
+
+    #[test]
+    fn deadzone_hides_drift_and_rescales_the_rest() {
+        assert_eq!(apply_deadzone(Vec2::new(0.1, 0.1)), Vec2::ZERO);
+        assert!((apply_deadzone(Vec2::new(0.6, 0.)).x - 0.5).abs() < 1e-5);
+        assert_eq!(apply_deadzone(Vec2::new(2., 0.)), Vec2::X);
+    }
+
+    #[test]
+    fn left_stick_moves_past_the_deadzone() {
+        let mut app = app();
+        connect(&mut app);
+
+        tilt(&mut app, GamepadAxisType::LeftStickX, 0.15);
+        app.update();
+        assert_eq!(analog(&app).movement, Vec2::ZERO);
+
+        tilt(&mut app, GamepadAxisType::LeftStickX, 1.);
+        app.update();
+        assert_eq!(analog(&app).movement, Vec2::X);
+        assert_eq!(analog(&app).aim, Vec2::ZERO);
+        assert_eq!(app.world().resource::<DeviceInputs>().get(InputDevice::Keyboard).analog.movement, Vec2::ZERO);
+    }
+
+    #[test]
+    fn buttons_drive_actions_of_their_own_gamepad_only() {
+        let mut app = app();
+        connect(&mut app);
+
+        press(&mut app, GamepadButtonType::South, 1.);
+        app.update();
+        let inputs = app.world().resource::<DeviceInputs>();
+        assert!(inputs.get(InputDevice::Gamepad(PAD)).actions.just_pressed(InputAction::Jump));
+        assert!(!inputs.get(InputDevice::Keyboard).actions.pressed(InputAction::Jump));
+        assert!(app.world().resource::<ButtonInput<InputAction>>().pressed(InputAction::Jump));
+    }
+
+    #[test]
+    fn unplugging_drops_the_gamepad_and_pauses() {
+        let mut app = app();
+        connect(&mut app);
+        press(&mut app, GamepadButtonType::RightTrigger2, 1.);
+        app.update();
+        assert!(app.world().resource::<DeviceInputs>().get(InputDevice::Gamepad(PAD)).actions.pressed(InputAction::Fire));
+
+        app.world_mut().send_event(GamepadConnectionEvent::new(PAD, GamepadConnection::Disconnected));
+        app.update();
+        assert!(!app.world().resource::<DeviceInputs>().get(InputDevice::Gamepad(PAD)).actions.pressed(InputAction::Fire));
+        app.update();
+        assert_eq!(*app.world().resource::<State<RunState>>().get(), RunState::Paused);
+        assert!(app.world().resource::<Time<Virtual>>().is_paused());
+
+        connect(&mut app);
+        assert!(app.world().resource::<DeviceInputs>().just_pressed(InputAction::Fire).next().is_none());
+    }
+
+    fn firing_app() -> App {
+        let mut app = app();
+        app
+            .init_resource::<Assets<Mesh>>()
+            .init_resource::<Assets<ColorMaterial>>()
+            .add_event::<PlayerMoveEvent>()
+            .add_systems(Update, spawn_bullets);
+        app.world_mut().spawn((Player::default(), Transform::default(), Stats::default(), Arsenal::default(), InputDevice::Gamepad(PAD)));
+        connect(&mut app);
+        app
+    }
+
+    fn step(app: &mut App) {
+        app.world_mut().resource_mut::<Time>().advance_by(Duration::from_millis(16));
+        app.update();
+    }
+
+    fn bullets(app: &mut App) -> Vec<Vec3> {
+        app.world_mut().query::<&Bullet>().iter(app.world()).map(|bullet| bullet.direction).collect()
+    }
+
+    #[test]
+    fn right_stick_alone_does_not_fire() {
+        let mut app = firing_app();
+        tilt(&mut app, GamepadAxisType::RightStickX, 1.);
+        step(&mut app);
+        assert!(bullets(&mut app).is_empty());
+    }
+
+    #[test]
+    fn trigger_fires_toward_the_right_stick() {
+        let mut app = firing_app();
+        tilt(&mut app, GamepadAxisType::RightStickY, 1.);
+        press(&mut app, GamepadButtonType::RightTrigger2, 1.);
+        step(&mut app);
+
+        let bullets = bullets(&mut app);
+        assert_eq!(bullets.len(), 1);
+        assert!(bullets[0].y > 0.5);
+    }
+}
@@ -0,0 +1,46 @@
+use bevy::prelude::*;
+
+use crate::{enemies::Death, RunState};
+
+type Mover = (&'static mut Transform, &'static mut Velocity, Option<&'static mut Acceleration>, Option<&'static Drag>);
+
+pub struct KinematicsPlugin;
+
+impl Plugin for KinematicsPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(Update, integrate_motion.run_if(in_state(RunState::Running)))
+        ;
+    }
+}
+
+/// Units per second the entity moves at, anything that pushes it writes here.
+#[derive(Component, Debug, Default, Clone, Copy, Deref, DerefMut)]
+pub struct Velocity(pub Vec2);
+
+/// Change of velocity per second, cleared after every step so forces are applied each frame.
+#[derive(Component, Debug, Default, Clone, Copy, Deref, DerefMut)]
+pub struct Acceleration(pub Vec2);
+
+/// Fraction of the velocity lost per second, the friction that brings pushes to a stop.
+#[derive(Component, Debug, Clone, Copy)]
+pub struct Drag(pub f32);
+
+/// Steps every moving entity; systems that set velocity or acceleration run before it.
+pub fn integrate_motion(
+    mut movers_query: Query<Mover, Without<Death>>,
+    time: Res<Time>,
+) {
+    let delta = time.delta_seconds();
+    for (mut transform, mut velocity, acceleration, drag) in &mut movers_query {
+        if let Some(mut acceleration) = acceleration {
+            velocity.0 += acceleration.0 * delta;
+            acceleration.0 = Vec2::ZERO;
+        }
+        transform.translation += (velocity.0 * delta).extend(0.);
+        // Drag acts after the step, so a velocity set every frame moves at full speed.
+        if let Some(drag) = drag {
+            velocity.0 *= (1. - drag.0 * delta).max(0.);
+        }
+    }
+}
@@ -1,14 +1,31 @@
-use bevy::{color::palettes::css::{BLUE, GRAY}, prelude::*};
+use bevy::{color::palettes::css::{BLUE, DARK_SLATE_GRAY, GRAY}, prelude::*};
+
+use crate::bounding::Shape;
+
+pub const FLOOR_SIZE: f32 = 5000.;
+
+const WALLS: [(Vec2, Vec2); 6] = [
+    (Vec2::new(450., 0.), Vec2::new(60., 700.)),
+    (Vec2::new(-450., 0.), Vec2::new(60., 700.)),
+    (Vec2::new(0., 500.), Vec2::new(600., 60.)),
+    (Vec2::new(0., -500.), Vec2::new(600., 60.)),
+    (Vec2::new(1100., 900.), Vec2::new(400., 400.)),
+    (Vec2::new(-1200., -800.), Vec2::new(300., 600.)),
+];
 
 pub struct LevelPlugin;
 
 impl Plugin for LevelPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, create_floor)
+        app.add_systems(Startup, (create_floor, create_walls))
         ;
     }
 }
 
+/// Static collider that steering and navigation treat as impassable.
+#[derive(Component)]
+pub struct Obstacle;
+
 pub fn create_floor(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
@@ -18,8 +35,30 @@ pub fn create_floor(
         ColorMesh2dBundle {
             mesh: meshes.add(Rectangle::default()).into(),
             material: materials.add(Color::from(GRAY)),
-            transform: Transform::default().with_scale(Vec3::new(5000., 5000., 0.01)).with_translation(Vec3::Z * -0.05),
+            transform: Transform::default().with_scale(Vec3::new(FLOOR_SIZE, FLOOR_SIZE, 0.01)).with_translation(Vec3::Z * -0.05),
             ..Default::default()
         }
     );
-}
\ No newline at end of file
+}
+
+pub fn create_walls(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let material = materials.add(Color::from(DARK_SLATE_GRAY));
+
+    for (position, size) in WALLS {
+        let rectangle = Rectangle::from_size(size);
+        commands.spawn((
+            ColorMesh2dBundle {
+                mesh: meshes.add(rectangle).into(),
+                material: material.clone(),
+                transform: Transform::from_translation(position.extend(-0.01)),
+                ..Default::default()
+            },
+            Obstacle,
+            Shape::Rectangle(rectangle),
+        ));
+    }
+}
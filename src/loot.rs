@@ -0,0 +1,276 @@
+use std::time::Duration;
+
+use bevy::{color::palettes::css::{GOLD, LIME, RED}, prelude::*, sprite::Mesh2dHandle, time::common_conditions::on_timer};
+use rand::Rng;
+
+use crate::{behaviour::Archetype, enemies::{Death, Health}, player::{Player, PLAYER_SIZE}, stats::{Stat, Stats}, upgrade::Experience, AppState, RunState};
+
+const PICKUP_RADIUS: f32 = 6.;
+const MAGNET_SPEED: f32 = 350.;
+const DROP_SCATTER: f32 = 20.;
+const PICKUP_LIFETIME: f32 = 30.;
+const MERGE_RADIUS: f32 = 40.;
+const MERGE_INTERVAL: f32 = 1.;
+/// Past this many pickups the oldest ones expire early.
+const MAX_PICKUPS: usize = 200;
+
+pub struct LootPlugin;
+
+impl Plugin for LootPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<LootAssets>()
+            .init_resource::<Collected>()
+            .add_systems(OnEnter(AppState::InGame), reset_collected)
+            .add_systems(OnExit(AppState::InGame), despawn_pickups)
+            .add_systems(Update, (
+                spawn_drops,
+                attract_pickups,
+                collect_pickups,
+                merge_pickups.run_if(on_timer(Duration::from_secs_f32(MERGE_INTERVAL))),
+                expire_pickups,
+            ).chain().run_if(in_state(RunState::Running)))
+        ;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LootKind {
+    Xp,
+    Health,
+    Coin,
+}
+
+#[derive(Clone, Copy)]
+pub struct LootDrop {
+    kind: LootKind,
+    chance: f32,
+    value: u32,
+}
+
+const fn drop(kind: LootKind, chance: f32, value: u32) -> LootDrop {
+    LootDrop { kind, chance, value }
+}
+
+const CHASER_DROPS: [LootDrop; 3] = [drop(LootKind::Xp, 0.8, 1), drop(LootKind::Coin, 0.2, 1), drop(LootKind::Health, 0.03, 1)];
+const FLANKER_DROPS: [LootDrop; 3] = [drop(LootKind::Xp, 0.9, 2), drop(LootKind::Coin, 0.3, 1), drop(LootKind::Health, 0.05, 1)];
+const SKIRMISHER_DROPS: [LootDrop; 3] = [drop(LootKind::Xp, 1., 3), drop(LootKind::Coin, 0.4, 2), drop(LootKind::Health, 0.08, 1)];
+pub const BOSS_DROPS: [LootDrop; 3] = [drop(LootKind::Xp, 1., 50), drop(LootKind::Coin, 1., 25), drop(LootKind::Health, 1., 1)];
+pub const NEST_DROPS: [LootDrop; 3] = [drop(LootKind::Xp, 1., 20), drop(LootKind::Coin, 1., 10), drop(LootKind::Health, 0.5, 1)];
+
+/// What an enemy may leave behind, each entry rolled independently on death.
+#[derive(Component)]
+pub struct DropTable(pub &'static [LootDrop]);
+
+impl DropTable {
+    pub fn for_archetype(archetype: Archetype) -> Self {
+        match archetype {
+            Archetype::Chaser => Self(&CHASER_DROPS),
+            Archetype::Flanker => Self(&FLANKER_DROPS),
+            Archetype::Skirmisher => Self(&SKIRMISHER_DROPS),
+        }
+    }
+}
+
+#[derive(Component)]
+struct Pickup {
+    kind: LootKind,
+    value: u32,
+    spawn_time: f32,
+}
+
+/// Coins picked up by the group during the current run, XP goes straight into `Experience`.
+#[derive(Resource, Default)]
+pub struct Collected {
+    pub coins: u32,
+}
+
+/// Shared mesh and materials so drops do not allocate assets.
+#[derive(Resource)]
+struct LootAssets {
+    mesh: Mesh2dHandle,
+    xp: Handle<ColorMaterial>,
+    health: Handle<ColorMaterial>,
+    coin: Handle<ColorMaterial>,
+}
+
+impl FromWorld for LootAssets {
+    fn from_world(world: &mut World) -> Self {
+        let mesh = world.resource_mut::<Assets<Mesh>>().add(Circle::new(PICKUP_RADIUS)).into();
+        let mut materials = world.resource_mut::<Assets<ColorMaterial>>();
+        Self {
+            mesh,
+            xp: materials.add(Color::from(LIME)),
+            health: materials.add(Color::from(RED)),
+            coin: materials.add(Color::from(GOLD)),
+        }
+    }
+}
+
+impl LootAssets {
+    fn material(&self, kind: LootKind) -> Handle<ColorMaterial> {
+        match kind {
+            LootKind::Xp => self.xp.clone(),
+            LootKind::Health => self.health.clone(),
+            LootKind::Coin => self.coin.clone(),
+        }
+    }
+}
+
+/// Merged pickups grow a little so bigger stacks stand out.
+fn pickup_scale(value: u32) -> Vec3 {
+    Vec3::splat((value as f32).sqrt().clamp(1., 3.))
+}
+
+fn reset_collected(mut collected: ResMut<Collected>) {
+    *collected = Collected::default();
+}
+
+fn spawn_drops(
+    mut commands: Commands,
+    dying_query: Query<(&Transform, &DropTable), Added<Death>>,
+    player_query: Query<&Stats, With<Player>>,
+    assets: Res<LootAssets>,
+    time: Res<Time>,
+) {
+    let mut rng = rand::thread_rng();
+    // The luckiest player rolls for the whole group.
+    let luck = player_query.iter().map(|stats| stats.get(Stat::Luck)).reduce(f32::max).unwrap_or(1.);
+
+    for (transform, table) in &dying_query {
+        for loot in table.0 {
+            if !rng.gen_bool((loot.chance * luck).clamp(0., 1.) as f64) {
+                continue;
+            }
+
+            let offset = Vec3::new(
+                rng.gen_range(-DROP_SCATTER..DROP_SCATTER),
+                rng.gen_range(-DROP_SCATTER..DROP_SCATTER),
+                -0.5,
+            );
+            commands.spawn((ColorMesh2dBundle {
+                mesh: assets.mesh.clone(),
+                material: assets.material(loot.kind),
+                transform: Transform::from_translation(transform.translation + offset).with_scale(pickup_scale(loot.value)),
+                ..default()
+            }, Pickup {
+                kind: loot.kind,
+                value: loot.value,
+                spawn_time: time.elapsed_seconds(),
+            }));
+        }
+    }
+}
+
+fn attract_pickups(
+    mut pickups_query: Query<&mut Transform, (With<Pickup>, Without<Player>)>,
+    player_query: Query<(&Transform, &Stats), With<Player>>,
+    time: Res<Time>,
+) {
+    let magnets: Vec<_> = player_query.iter().map(|(transform, stats)| (transform.translation.truncate(), stats.get(Stat::PickupRadius))).collect();
+
+    for mut transform in &mut pickups_query {
+        let position = transform.translation.truncate();
+        // Flies to the closest player whose magnet reaches it.
+        let Some((offset, magnet_radius)) = magnets.iter()
+            .map(|&(player_position, radius)| (player_position - position, radius))
+            .filter(|(offset, radius)| offset.length() <= *radius)
+            .min_by(|(a, _), (b, _)| a.length_squared().total_cmp(&b.length_squared())) else {
+            continue;
+        };
+        let distance = offset.length();
+        if distance == 0. {
+            continue;
+        }
+        // Pull harder the closer the pickup gets, without overshooting the player.
+        let pull = MAGNET_SPEED * (1. - distance / magnet_radius).max(0.2) * time.delta_seconds();
+        transform.translation += (offset / distance * pull.min(distance)).extend(0.);
+    }
+}
+
+fn collect_pickups(
+    mut commands: Commands,
+    pickups_query: Query<(Entity, &Transform, &Pickup), Without<Player>>,
+    mut player_query: Query<(&Transform, &mut Health), With<Player>>,
+    mut collected: ResMut<Collected>,
+    mut experience: ResMut<Experience>,
+) {
+    let reach = PLAYER_SIZE / 2. + PICKUP_RADIUS;
+    for (entity, transform, pickup) in &pickups_query {
+        let Some((_, mut player_health)) = player_query.iter_mut().find(|(player_transform, _)| {
+            player_transform.translation.truncate().distance_squared(transform.translation.truncate()) <= reach * reach
+        }) else {
+            continue;
+        };
+
+        match pickup.kind {
+            LootKind::Xp => experience.gain(pickup.value),
+            LootKind::Coin => collected.coins += pickup.value,
+            LootKind::Health => {
+                player_health.heal(pickup.value as i32);
+            }
+        }
+        commands.entity(entity).despawn();
+    }
+}
+
+fn merge_pickups(
+    mut commands: Commands,
+    mut pickups_query: Query<(Entity, &mut Transform, &mut Pickup)>,
+) {
+    let mut pickups: Vec<_> = pickups_query.iter().map(|(entity, transform, pickup)| (entity, transform.translation.truncate(), pickup.kind)).collect();
+    // Earlier survivors are never within reach of later ones, so `keep` stays at `index`.
+    let mut index = 0;
+    while index < pickups.len() {
+        let (keep, position, kind) = pickups[index];
+        let mut absorbed = 0;
+        let mut newest = f32::NEG_INFINITY;
+        pickups.retain(|&(other, other_position, other_kind)| {
+            if other == keep || other_kind != kind || position.distance_squared(other_position) > MERGE_RADIUS * MERGE_RADIUS {
+                return true;
+            }
+            if let Ok((_, _, pickup)) = pickups_query.get(other) {
+                absorbed += pickup.value;
+                newest = newest.max(pickup.spawn_time);
+            }
+            commands.entity(other).despawn();
+            false
+        });
+        index += 1;
+
+        if absorbed > 0 {
+            if let Ok((_, mut transform, mut pickup)) = pickups_query.get_mut(keep) {
+                pickup.value += absorbed;
+                pickup.spawn_time = pickup.spawn_time.max(newest);
+                transform.scale = pickup_scale(pickup.value);
+            }
+        }
+    }
+}
+
+fn expire_pickups(
+    mut commands: Commands,
+    pickups_query: Query<(Entity, &Pickup)>,
+    time: Res<Time>,
+) {
+    let mut pickups: Vec<_> = pickups_query.iter().map(|(entity, pickup)| (entity, pickup.spawn_time)).collect();
+    let overflow = pickups.len().saturating_sub(MAX_PICKUPS);
+    if overflow > 0 {
+        pickups.sort_by(|a, b| a.1.total_cmp(&b.1));
+    }
+
+    for (index, (entity, spawn_time)) in pickups.into_iter().enumerate() {
+        if index < overflow || time.elapsed_seconds() - spawn_time > PICKUP_LIFETIME {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+fn despawn_pickups(
+    mut commands: Commands,
+    pickups_query: Query<Entity, With<Pickup>>,
+) {
+    for entity in &pickups_query {
+        commands.entity(entity).despawn();
+    }
+}
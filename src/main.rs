@@ -1,13 +1,33 @@
+use attack::AttackPlugin;
+use behaviour::BehaviourPlugin;
 use bevy::prelude::*;
+use boss::BossPlugin;
 use bounding::BoundingPlugin;
 use bullet::BulletPlugin;
 use camera::CameraPlugin;
+use character::CharacterPlugin;
+use damage::DamagePlugin;
+use dash::DashPlugin;
 use death::DeathPlugin;
+use elite::ElitePlugin;
 use enemies::EnemyPlugin;
+use formation::FormationPlugin;
 use game::GamePlugin;
+use health_bar::HealthBarPlugin;
+use input::InputPlugin;
+use kinematics::KinematicsPlugin;
 use level::LevelPlugin;
+use loot::LootPlugin;
 use menu::MenuPlugin;
+use navigation::NavigationPlugin;
+use perception::PerceptionPlugin;
+use nest::NestPlugin;
 use player::PlayerPlugin;
+use shatter::ShatterPlugin;
+use stats::StatsPlugin;
+use status::StatusPlugin;
+use upgrade::UpgradePlugin;
+use steering::SteeringPlugin;
 
 mod player;
 mod camera;
@@ -18,6 +38,26 @@ mod bounding;
 mod menu;
 mod death;
 mod game;
+mod steering;
+mod navigation;
+mod behaviour;
+mod boss;
+mod health_bar;
+mod shatter;
+mod loot;
+mod elite;
+mod status;
+mod damage;
+mod formation;
+mod nest;
+mod attack;
+mod perception;
+mod input;
+mod dash;
+mod stats;
+mod upgrade;
+mod kinematics;
+mod character;
 
 #[derive(Debug, Clone, Copy, Default, Eq, PartialEq, Hash, States)]
 pub enum AppState {
@@ -27,9 +67,21 @@ pub enum AppState {
     EndGame,
 }
 
+/// Whether a run is being played or held, gameplay systems only run while `Running`.
+#[derive(SubStates, Debug, Clone, Copy, Default, Eq, PartialEq, Hash)]
+#[source(AppState = AppState::InGame)]
+pub enum RunState {
+    #[default]
+    Running,
+    Paused,
+    /// The upgrade choice screen is open.
+    LevelUp,
+}
+
 fn main() {
     App::new()
         .init_state::<AppState>()
+        .add_sub_state::<RunState>()
         .add_plugins(DefaultPlugins)
         .add_plugins(PlayerPlugin)
         .add_plugins(CameraPlugin)
@@ -40,5 +92,25 @@ fn main() {
         .add_plugins(MenuPlugin)
         .add_plugins(DeathPlugin)
         .add_plugins(GamePlugin)
+        .add_plugins(SteeringPlugin)
+        .add_plugins(NavigationPlugin)
+        .add_plugins(BehaviourPlugin)
+        .add_plugins(BossPlugin)
+        .add_plugins(HealthBarPlugin)
+        .add_plugins(ShatterPlugin)
+        .add_plugins(LootPlugin)
+        .add_plugins(ElitePlugin)
+        .add_plugins(StatusPlugin)
+        .add_plugins(DamagePlugin)
+        .add_plugins(FormationPlugin)
+        .add_plugins(NestPlugin)
+        .add_plugins(AttackPlugin)
+        .add_plugins(PerceptionPlugin)
+        .add_plugins(InputPlugin)
+        .add_plugins(DashPlugin)
+        .add_plugins(StatsPlugin)
+        .add_plugins(UpgradePlugin)
+        .add_plugins(KinematicsPlugin)
+        .add_plugins(CharacterPlugin)
         .run();
 }
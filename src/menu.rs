@@ -1,6 +1,6 @@
 use bevy::prelude::*;
 
-use crate::AppState;
+use crate::{character::{Pick, Roster, CHARACTERS, MAX_PLAYERS}, input::{DeviceInputs, InputAction, InputDevice}, AppState};
 
 const NORMAL_BUTTON: Color = Color::srgb(0.15, 0.15, 0.15);
 const HOVERED_BUTTON: Color = Color::srgb(0.25, 0.25, 0.25);
@@ -21,76 +21,196 @@ impl Plugin for MenuPlugin {
 #[derive(Resource)]
 struct MenuData {
     button_entity: Entity,
+    /// Index into `CHARACTERS` of the card keyboard and gamepad act on.
+    focus: usize,
 }
 
+#[derive(Component)]
+struct CharacterButton(usize);
+
+/// Lists who has joined and with what.
+#[derive(Component)]
+struct RosterText;
+
 fn setup_menu(
-    mut commands: Commands
+    mut commands: Commands,
+    mut roster: ResMut<Roster>,
 ) {
+    roster.0.clear();
     let button_entity = commands
     .spawn(NodeBundle {
         style: Style {
-            // center button
+            // center the character cards
             width: Val::Percent(100.),
             height: Val::Percent(100.),
+            flex_direction: FlexDirection::Column,
             justify_content: JustifyContent::Center,
             align_items: AlignItems::Center,
+            row_gap: Val::Px(30.),
             ..default()
         },
         ..default()
     })
     .with_children(|parent| {
+        parent.spawn(TextBundle::from_section(
+            "Choose your character",
+            TextStyle {
+                font_size: 48.0,
+                color: Color::srgb(0.9, 0.9, 0.9),
+                ..default()
+            },
+        ));
+
         parent
-            .spawn(ButtonBundle {
+            .spawn(NodeBundle {
                 style: Style {
-                    width: Val::Px(150.),
-                    height: Val::Px(65.),
-                    // horizontally center child text
-                    justify_content: JustifyContent::Center,
-                    // vertically center child text
-                    align_items: AlignItems::Center,
+                    column_gap: Val::Px(20.),
                     ..default()
                 },
-                image: UiImage::default().with_color(NORMAL_BUTTON),
                 ..default()
             })
             .with_children(|parent| {
-                parent.spawn(TextBundle::from_section(
-                    "Play",
-                    TextStyle {
-                        font_size: 40.0,
-                        color: Color::srgb(0.9, 0.9, 0.9),
-                        ..default()
-                    },
-                ));
+                for (index, character) in CHARACTERS.into_iter().enumerate() {
+                    parent
+                        .spawn((ButtonBundle {
+                            style: Style {
+                                width: Val::Px(240.),
+                                height: Val::Px(200.),
+                                flex_direction: FlexDirection::Column,
+                                justify_content: JustifyContent::Center,
+                                align_items: AlignItems::Center,
+                                row_gap: Val::Px(12.),
+                                padding: UiRect::all(Val::Px(10.)),
+                                border: UiRect::all(Val::Px(3.)),
+                                ..default()
+                            },
+                            border_color: character.color().into(),
+                            image: UiImage::default().with_color(if index == 0 { HOVERED_BUTTON } else { NORMAL_BUTTON }),
+                            ..default()
+                        }, CharacterButton(index)))
+                        .with_children(|parent| {
+                            parent.spawn(NodeBundle {
+                                style: Style {
+                                    width: Val::Px(40.),
+                                    height: Val::Px(40.),
+                                    ..default()
+                                },
+                                background_color: character.color().into(),
+                                ..default()
+                            });
+                            parent.spawn(TextBundle::from_section(
+                                character.name(),
+                                TextStyle {
+                                    font_size: 32.0,
+                                    color: character.color(),
+                                    ..default()
+                                },
+                            ));
+                            parent.spawn(TextBundle::from_section(
+                                character.description(),
+                                TextStyle {
+                                    font_size: 16.0,
+                                    color: Color::srgb(0.9, 0.9, 0.9),
+                                    ..default()
+                                },
+                            ).with_text_justify(JustifyText::Center));
+                        });
+                }
             });
+
+        parent.spawn(TextBundle::from_section(
+            "Confirm on any keyboard or gamepad to join, again to start",
+            TextStyle {
+                font_size: 20.0,
+                color: Color::srgb(0.6, 0.6, 0.6),
+                ..default()
+            },
+        ));
+        parent.spawn((TextBundle::from_section(
+            "",
+            TextStyle {
+                font_size: 24.0,
+                color: Color::srgb(0.9, 0.9, 0.9),
+                ..default()
+            },
+        ), RosterText));
     })
     .id();
-commands.insert_resource(MenuData { button_entity });
+commands.insert_resource(MenuData { button_entity, focus: 0 });
 }
 
 fn menu(
     mut next_state: ResMut<NextState<AppState>>,
-    mut interaction_query: Query<
-        (&Interaction, &mut UiImage),
-        (Changed<Interaction>, With<Button>),
-    >,
+    mut menu_data: ResMut<MenuData>,
+    mut roster: ResMut<Roster>,
+    mut interaction_query: Query<(Ref<Interaction>, &CharacterButton, &mut UiImage)>,
+    mut roster_text_query: Query<&mut Text, With<RosterText>>,
+    actions: Res<ButtonInput<InputAction>>,
+    inputs: Res<DeviceInputs>,
 ) {
-    for (interaction, mut image) in &mut interaction_query {
+    let mut confirmed: Vec<_> = inputs.just_pressed(InputAction::Confirm).collect();
+    for (interaction, button, _) in &interaction_query {
         match *interaction {
-            Interaction::Pressed => {
-                image.color = PRESSED_BUTTON;
-                next_state.set(AppState::InGame);
-            }
-            Interaction::Hovered => {
-                image.color = HOVERED_BUTTON;
-            }
-            Interaction::None => {
-                image.color = NORMAL_BUTTON;
+            // Clicking a card confirms it for the keyboard player.
+            Interaction::Pressed if interaction.is_changed() => {
+                menu_data.focus = button.0;
+                if !confirmed.contains(&InputDevice::Keyboard) {
+                    confirmed.push(InputDevice::Keyboard);
+                }
             }
+            Interaction::Hovered => menu_data.focus = button.0,
+            _ => {}
+        }
+    }
+    let count = CHARACTERS.len();
+    if actions.just_pressed(InputAction::MoveRight) {
+        menu_data.focus = (menu_data.focus + 1) % count;
+    }
+    if actions.just_pressed(InputAction::MoveLeft) {
+        menu_data.focus = (menu_data.focus + count - 1) % count;
+    }
+
+    // A device's first confirm joins with the focused card, the next one starts the run.
+    let mut start = false;
+    for device in confirmed {
+        if roster.contains(device) {
+            start = true;
+        } else if roster.0.len() < MAX_PLAYERS {
+            roster.0.push(Pick { device, character: CHARACTERS[menu_data.focus] });
         }
     }
+
+    for (_, button, mut image) in &mut interaction_query {
+        let taken = roster.0.iter().any(|pick| pick.character == CHARACTERS[button.0]);
+        image.color = if button.0 == menu_data.focus {
+            HOVERED_BUTTON
+        } else if taken {
+            PRESSED_BUTTON
+        } else {
+            NORMAL_BUTTON
+        };
+    }
+
+    if roster.is_changed() {
+        if let Ok(mut text) = roster_text_query.get_single_mut() {
+            text.sections[0].value = roster.0.iter().enumerate()
+                .map(|(index, pick)| {
+                    let device = match pick.device {
+                        InputDevice::Keyboard => "keyboard".to_string(),
+                        InputDevice::Gamepad(gamepad) => format!("gamepad {}", gamepad.id),
+                    };
+                    format!("P{} {} ({device})", index + 1, pick.character.name())
+                })
+                .collect::<Vec<_>>()
+                .join("    ");
+        }
+    }
+
+    if start {
+        next_state.set(AppState::InGame);
+    }
 }
 
 fn cleanup_menu(mut commands: Commands, menu_data: Res<MenuData>) {
     commands.entity(menu_data.button_entity).despawn_recursive();
-}
\ No newline at end of file
+}
@@ -0,0 +1,162 @@
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+
+use crate::{bounding::Volume, level::{Obstacle, FLOOR_SIZE}, player::Player, RunState};
+
+const CELL_SIZE: f32 = 50.;
+const AGENT_RADIUS: f32 = 20.;
+const UNREACHABLE: u32 = u32::MAX;
+
+pub struct NavigationPlugin;
+
+impl Plugin for NavigationPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<NavGrid>()
+            .init_resource::<FlowField>()
+            .add_systems(Update, (build_nav_grid, update_flow_field).chain().run_if(in_state(RunState::Running)))
+        ;
+    }
+}
+
+/// Walkable cells covering the floor, rebuilt whenever an obstacle collider changes.
+#[derive(Resource)]
+pub struct NavGrid {
+    width: i32,
+    height: i32,
+    origin: Vec2,
+    blocked: Vec<bool>,
+}
+
+impl Default for NavGrid {
+    fn default() -> Self {
+        let cells = (FLOOR_SIZE / CELL_SIZE).ceil() as i32;
+        Self {
+            width: cells,
+            height: cells,
+            origin: Vec2::splat(-FLOOR_SIZE / 2.),
+            blocked: vec![false; (cells * cells) as usize],
+        }
+    }
+}
+
+impl NavGrid {
+    pub fn cell(&self, position: Vec2) -> Option<IVec2> {
+        let cell = ((position - self.origin) / CELL_SIZE).floor().as_ivec2();
+        self.contains(cell).then_some(cell)
+    }
+
+    pub fn center(&self, cell: IVec2) -> Vec2 {
+        self.origin + (cell.as_vec2() + 0.5) * CELL_SIZE
+    }
+
+    pub fn is_walkable(&self, cell: IVec2) -> bool {
+        self.contains(cell) && !self.blocked[self.index(cell)]
+    }
+
+    fn contains(&self, cell: IVec2) -> bool {
+        cell.x >= 0 && cell.y >= 0 && cell.x < self.width && cell.y < self.height
+    }
+
+    fn index(&self, cell: IVec2) -> usize {
+        (cell.y * self.width + cell.x) as usize
+    }
+}
+
+/// Breadth-first distance from every walkable cell to the nearest player's cell, shared by all enemies.
+#[derive(Resource, Default)]
+pub struct FlowField {
+    targets: Vec<IVec2>,
+    costs: Vec<u32>,
+}
+
+impl FlowField {
+    /// Center of the neighbouring cell closest to the target, or `None` when the
+    /// agent already shares the target's cell or cannot reach it.
+    pub fn waypoint(&self, grid: &NavGrid, position: Vec2) -> Option<Vec2> {
+        let cell = grid.cell(position)?;
+        let cost = *self.costs.get(grid.index(cell))?;
+        if cost == 0 || cost == UNREACHABLE {
+            return None;
+        }
+
+        let mut best = None;
+        let mut best_cost = cost;
+        for offset in [
+            IVec2::X, IVec2::NEG_X, IVec2::Y, IVec2::NEG_Y,
+            IVec2::ONE, IVec2::NEG_ONE, IVec2::new(1, -1), IVec2::new(-1, 1),
+        ] {
+            let next = cell + offset;
+            if !grid.is_walkable(next) {
+                continue;
+            }
+            // Diagonal moves must not clip the corner of a blocked cell.
+            if offset.x != 0 && offset.y != 0
+                && !(grid.is_walkable(cell + IVec2::new(offset.x, 0)) && grid.is_walkable(cell + IVec2::new(0, offset.y))) {
+                continue;
+            }
+            let next_cost = self.costs[grid.index(next)];
+            if next_cost < best_cost {
+                best_cost = next_cost;
+                best = Some(next);
+            }
+        }
+        best.map(|next| grid.center(next))
+    }
+}
+
+pub fn build_nav_grid(
+    mut grid: ResMut<NavGrid>,
+    changed_query: Query<(), (With<Obstacle>, Changed<Volume>)>,
+    obstacles_query: Query<&Volume, With<Obstacle>>,
+) {
+    if changed_query.is_empty() {
+        return;
+    }
+
+    let mut blocked = vec![false; grid.blocked.len()];
+    for volume in &obstacles_query {
+        let min = ((volume.min - AGENT_RADIUS - grid.origin) / CELL_SIZE).floor().as_ivec2().max(IVec2::ZERO);
+        let max = ((volume.max + AGENT_RADIUS - grid.origin) / CELL_SIZE).floor().as_ivec2()
+            .min(IVec2::new(grid.width - 1, grid.height - 1));
+        for y in min.y..=max.y {
+            for x in min.x..=max.x {
+                blocked[grid.index(IVec2::new(x, y))] = true;
+            }
+        }
+    }
+    grid.blocked = blocked;
+}
+
+pub fn update_flow_field(
+    grid: Res<NavGrid>,
+    mut field: ResMut<FlowField>,
+    player_query: Query<&Transform, With<Player>>,
+) {
+    let mut targets: Vec<_> = player_query.iter().filter_map(|transform| grid.cell(transform.translation.xy())).collect();
+    targets.sort_by_key(|cell| (cell.x, cell.y));
+    targets.dedup();
+    if targets == field.targets && !grid.is_changed() {
+        return;
+    }
+
+    // Every player cell seeds the search, so each cell ends up pointing at the closest one.
+    let mut costs = vec![UNREACHABLE; grid.blocked.len()];
+    for &target in &targets {
+        costs[grid.index(target)] = 0;
+    }
+    let mut frontier = VecDeque::from(targets.clone());
+    while let Some(cell) = frontier.pop_front() {
+        let cost = costs[grid.index(cell)];
+        for offset in [IVec2::X, IVec2::NEG_X, IVec2::Y, IVec2::NEG_Y] {
+            let next = cell + offset;
+            if grid.is_walkable(next) && costs[grid.index(next)] == UNREACHABLE {
+                costs[grid.index(next)] = cost + 1;
+                frontier.push_back(next);
+            }
+        }
+    }
+    field.targets = targets;
+    field.costs = costs;
+}
@@ -0,0 +1,121 @@
+use std::f32::consts::TAU;
+
+use bevy::{color::palettes::css::{DARK_MAGENTA, PURPLE}, prelude::*};
+use rand::Rng;
+
+use crate::{behaviour::Archetype, bounding::{Intersects, Shape}, damage::Bounty, enemies::{CappedEnemy, Death, Enemy, Health, SpawnTelegraph, TelegraphAssets, MAX_ENEMIES, TELEGRAPH_TIME}, loot::{DropTable, NEST_DROPS}, player::Player, AppState, RunState};
+
+const NEST_POSITIONS: [Vec2; 4] = [
+    Vec2::new(-1800., 1500.),
+    Vec2::new(1700., -1600.),
+    Vec2::new(200., 2000.),
+    Vec2::new(-2000., -1900.),
+];
+const NEST_RADIUS: f32 = 45.;
+const NEST_HEALTH: i32 = 25;
+const NEST_BOUNTY: i32 = 25;
+const NEST_INTERVAL: f32 = 6.;
+const NEST_BROOD: usize = 2;
+/// Nests only produce enemies while the player is within this distance.
+const NEST_ACTIVE_DISTANCE: f32 = 1500.;
+
+pub struct NestPlugin;
+
+impl Plugin for NestPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(OnEnter(AppState::InGame), spawn_nests)
+            .add_systems(OnExit(AppState::InGame), despawn_nests)
+            .add_systems(Update, (pulse_nests, spawn_from_nests).run_if(in_state(RunState::Running)))
+        ;
+    }
+}
+
+/// Static enemy that keeps hatching others until it is destroyed.
+#[derive(Component)]
+pub struct Nest {
+    timer: Timer,
+}
+
+fn spawn_nests(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    let mesh = meshes.add(RegularPolygon::new(NEST_RADIUS, 5));
+
+    for position in NEST_POSITIONS {
+        commands.spawn((
+            ColorMesh2dBundle {
+                mesh: mesh.clone().into(),
+                material: materials.add(Color::from(DARK_MAGENTA)),
+                transform: Transform::from_translation(position.extend(-0.01)),
+                ..default()
+            },
+            Enemy,
+            Nest {
+                timer: Timer::from_seconds(NEST_INTERVAL, TimerMode::Repeating),
+            },
+            Health::new(NEST_HEALTH),
+            Bounty(NEST_BOUNTY),
+            DropTable(&NEST_DROPS),
+            Shape::Ellipse(Ellipse::new(NEST_RADIUS, NEST_RADIUS)),
+            Intersects::default(),
+        ));
+    }
+}
+
+/// Clears what is left of this run's nests so the next one starts with all of them.
+fn despawn_nests(
+    mut commands: Commands,
+    nests_query: Query<Entity, With<Nest>>,
+) {
+    for entity in &nests_query {
+        commands.entity(entity).despawn();
+    }
+}
+
+/// Flashes the nest brighter as its next brood gets close.
+fn pulse_nests(
+    nests_query: Query<(&Nest, &Handle<ColorMaterial>), Without<Death>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    for (nest, material_handle) in &nests_query {
+        if let Some(material) = materials.get_mut(material_handle) {
+            material.color = Color::from(DARK_MAGENTA).mix(&Color::from(PURPLE), nest.timer.fraction());
+        }
+    }
+}
+
+fn spawn_from_nests(
+    mut commands: Commands,
+    mut nests_query: Query<(&mut Nest, &Transform), Without<Death>>,
+    enemies_query: Query<(), CappedEnemy>,
+    player_query: Query<&Transform, With<Player>>,
+    telegraph_assets: Res<TelegraphAssets>,
+    time: Res<Time>,
+) {
+    let players: Vec<_> = player_query.iter().map(|transform| transform.translation.truncate()).collect();
+
+    let mut alive = enemies_query.iter().count();
+    let mut rng = rand::thread_rng();
+    for (mut nest, transform) in &mut nests_query {
+        let position = transform.translation.truncate();
+        if players.iter().all(|player| position.distance(*player) > NEST_ACTIVE_DISTANCE) {
+            continue;
+        }
+        if !nest.timer.tick(time.delta()).just_finished() || alive + NEST_BROOD > MAX_ENEMIES {
+            continue;
+        }
+
+        for _ in 0..NEST_BROOD {
+            let offset = Vec2::from_angle(rng.gen_range(0.0..TAU)) * NEST_RADIUS * 1.5;
+            let archetype = if rng.gen_bool(0.2) { Archetype::Flanker } else { Archetype::Chaser };
+            commands.spawn((
+                telegraph_assets.bundle(position + offset),
+                SpawnTelegraph::new(archetype, time.elapsed_seconds() + TELEGRAPH_TIME),
+            ));
+        }
+        alive += NEST_BROOD;
+    }
+}
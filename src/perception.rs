@@ -0,0 +1,181 @@
+use std::f32::consts::TAU;
+
+use bevy::{color::palettes::css::{GRAY, RED, YELLOW}, math::bounding::RayCast2d, prelude::*};
+use rand::Rng;
+
+use crate::{behaviour::{tick_behaviours, Archetype, BehaviourDebug}, bounding::Volume, damage::DamageEvent, enemies::{move_enemies, Death, NearEnemy}, level::Obstacle, navigation::NavGrid, player::Player, AppState, RunState};
+
+/// Seconds an enemy keeps searching after losing sight of the player.
+const SEARCH_TIME: f32 = 5.;
+const SEARCH_RADIUS: f32 = 150.;
+const WANDER_RADIUS: f32 = 250.;
+const WANDER_INTERVAL: f32 = 3.;
+/// Fraction of their speed enemies move at while not chasing anyone.
+pub const WANDER_SPEED: f32 = 0.5;
+
+pub struct PerceptionPlugin;
+
+impl Plugin for PerceptionPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(Update, update_perception.before(tick_behaviours).before(move_enemies).run_if(in_state(RunState::Running)))
+            .add_systems(Update, draw_perception.run_if(|debug: Res<BehaviourDebug>| debug.0).run_if(in_state(AppState::InGame)))
+        ;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Awareness {
+    /// Has not seen the player, wanders around where it spawned.
+    Idle,
+    /// Sees the player and follows its behaviour.
+    Alert,
+    /// Lost the player and looks around the last known position.
+    Searching,
+}
+
+/// What an enemy knows about the player.
+#[derive(Component, Debug)]
+pub struct Perception {
+    radius: f32,
+    /// Whether obstacles block the view.
+    line_of_sight: bool,
+    pub awareness: Awareness,
+    last_known: Option<Vec2>,
+    lost_at: f32,
+    home: Option<Vec2>,
+    /// Point the enemy heads to while idle or searching.
+    wander: Option<Vec2>,
+    next_wander: f32,
+}
+
+impl Perception {
+    pub fn new(radius: f32, line_of_sight: bool) -> Self {
+        Self {
+            radius,
+            line_of_sight,
+            awareness: Awareness::Idle,
+            last_known: None,
+            lost_at: 0.,
+            home: None,
+            wander: None,
+            next_wander: 0.,
+        }
+    }
+
+    /// Every radius stays inside the closest regular spawns, half a view height plus the spawn margin,
+    /// so enemies hatch idle and only engage once a player comes into range.
+    pub fn for_archetype(archetype: Archetype) -> Self {
+        match archetype {
+            Archetype::Chaser => Self::new(350., true),
+            Archetype::Flanker => Self::new(300., true),
+            Archetype::Skirmisher => Self::new(400., false),
+        }
+    }
+
+    pub fn is_aware(&self) -> bool {
+        self.awareness == Awareness::Alert
+    }
+
+    /// Where to move instead of following the behaviour, `None` while alert.
+    pub fn target(&self) -> Option<Vec2> {
+        if self.is_aware() { None } else { self.wander }
+    }
+
+    fn alert(&mut self, player_position: Vec2) {
+        self.awareness = Awareness::Alert;
+        self.last_known = Some(player_position);
+        self.wander = None;
+    }
+}
+
+fn update_perception(
+    mut enemies_query: Query<(Entity, &Transform, &mut Perception), NearEnemy>,
+    player_query: Query<(Entity, &Transform), With<Player>>,
+    obstacles_query: Query<&Volume, With<Obstacle>>,
+    mut damage_events: EventReader<DamageEvent>,
+    nav_grid: Res<NavGrid>,
+    time: Res<Time>,
+) {
+    let now = time.elapsed_seconds();
+
+    // Enemies shot by a player notice them wherever they are.
+    for event in damage_events.read() {
+        let Some(Ok((_, player_transform))) = event.source.map(|source| player_query.get(source)) else {
+            continue;
+        };
+        if let Ok((_, _, mut perception)) = enemies_query.get_mut(event.target) {
+            perception.alert(player_transform.translation.truncate());
+        }
+    }
+
+    let mut rng = rand::thread_rng();
+    for (_, transform, mut perception) in &mut enemies_query {
+        let position = transform.translation.truncate();
+        let home = *perception.home.get_or_insert(position);
+
+        // The closest player in view is the one the enemy goes after.
+        let seen = player_query.iter()
+            .map(|(_, player_transform)| player_transform.translation.truncate())
+            .filter(|player_position| {
+                let offset = *player_position - position;
+                offset.length() <= perception.radius
+                    && (!perception.line_of_sight || Dir2::new(offset).map_or(true, |direction| {
+                        let ray = RayCast2d::new(position, direction, offset.length());
+                        !obstacles_query.iter().any(|volume| ray.aabb_intersection_at(&volume.0).is_some())
+                    }))
+            })
+            .min_by(|a, b| a.distance_squared(position).total_cmp(&b.distance_squared(position)));
+
+        if let Some(player_position) = seen {
+            perception.alert(player_position);
+            continue;
+        }
+
+        match perception.awareness {
+            Awareness::Alert => {
+                perception.awareness = Awareness::Searching;
+                perception.lost_at = now;
+                perception.wander = perception.last_known;
+                perception.next_wander = now + WANDER_INTERVAL;
+            }
+            Awareness::Searching if now - perception.lost_at > SEARCH_TIME => {
+                perception.awareness = Awareness::Idle;
+                perception.home = Some(position);
+                perception.wander = None;
+            }
+            _ => {}
+        }
+
+        if now < perception.next_wander {
+            continue;
+        }
+        let (center, radius) = match perception.awareness {
+            Awareness::Searching => (perception.last_known.unwrap_or(position), SEARCH_RADIUS),
+            _ => (home, WANDER_RADIUS),
+        };
+        let candidate = center + Vec2::from_angle(rng.gen_range(0.0..TAU)) * rng.gen_range(0.0..radius);
+        if nav_grid.cell(candidate).is_some_and(|cell| nav_grid.is_walkable(cell)) {
+            perception.wander = Some(candidate);
+        }
+        perception.next_wander = now + rng.gen_range(0.5..1.5) * WANDER_INTERVAL;
+    }
+}
+
+fn draw_perception(
+    mut gizmos: Gizmos,
+    enemies_query: Query<(&Transform, &Perception), Without<Death>>,
+) {
+    for (transform, perception) in &enemies_query {
+        let position = transform.translation.truncate();
+        let color = match perception.awareness {
+            Awareness::Idle => Color::from(GRAY),
+            Awareness::Alert => Color::from(RED),
+            Awareness::Searching => Color::from(YELLOW),
+        };
+        gizmos.circle_2d(position, perception.radius, color.with_alpha(0.2));
+        if let Some(wander) = perception.target() {
+            gizmos.line_2d(position, wander, color);
+        }
+    }
+}
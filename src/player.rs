@@ -1,31 +1,50 @@
 
-use bevy::{animation::{AnimationTarget, AnimationTargetId}, color::palettes::css::{PURPLE, YELLOW}, prelude::*, sprite::MaterialMesh2dBundle};
+use bevy::{animation::{AnimationTarget, AnimationTargetId}, color::palettes::css::YELLOW, prelude::*, sprite::MaterialMesh2dBundle};
 
-use crate::{bounding::Shape, enemies::Health, AppState};
+use crate::{character::{Character, Roster}, damage::{Critical, IFrames}, dash::Dash, enemies::Health, input::{AnalogInput, DeviceInputs, InputAction, InputDevice}, kinematics::{integrate_motion, Acceleration, Velocity}, stats::{Stat, Stats}, status::StatusEffects, AppState, RunState};
 
 pub const PLAYER_SIZE: f32 = 64.;
-pub const PLAYER_LIFES: i8 = 3;
-const SPEED: f32 = 200.;
 const JUMP_SPEED: f32 = 0.5;
+const IFRAME_TIME: f32 = 1.;
+/// How fast the player speeds up and slows down, in units per second squared.
+const ACCELERATION: f32 = 1800.;
+const DECELERATION: f32 = 2400.;
+/// Distance between players when a co-op run starts.
+const PLAYER_SPACING: f32 = 120.;
+
+type PlayerMover = (
+    Entity,
+    &'static InputDevice,
+    &'static Velocity,
+    &'static mut Acceleration,
+    &'static Stats,
+    Option<&'static StatusEffects>,
+    &'static Dash,
+);
 
 pub struct PlayerPlugin;
 
 #[derive(Component, Default)]
 pub struct Player {
-    is_jumping: bool
+    is_jumping: bool,
+    /// Join order, 0 for the first player.
+    pub index: usize,
 }
 
 #[derive(Resource, Default, Deref, DerefMut)]
 pub struct PlayerAnimations(Vec<AnimationNodeIndex>);
 
-#[derive(Event, Default)]
-pub struct PlayerJumpStartEvent;
+#[derive(Event)]
+pub struct PlayerJumpStartEvent(pub Entity);
 
-#[derive(Event, Default)]
-pub struct PlayerJumpEndEvent;
+#[derive(Event)]
+pub struct PlayerJumpEndEvent(pub Entity);
 
-#[derive(Event, Default)]
-pub struct PlayerMoveEvent(pub Vec3);
+#[derive(Event)]
+pub struct PlayerMoveEvent {
+    pub player: Entity,
+    pub direction: Vec3,
+}
 
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
@@ -34,179 +53,225 @@ impl Plugin for PlayerPlugin {
         .add_event::<PlayerJumpStartEvent>()
         .add_event::<PlayerJumpEndEvent>()
         .add_event::<PlayerMoveEvent>()
-        .add_event::<PlayerHitEvent>()
-        .add_systems(Startup, (spawn_player, generate_jump_animation).chain())
-        .add_systems(Startup, generate_jump_animation)
-        .add_systems(Update, (move_player, jump_player, change_color, player_death).run_if(in_state(AppState::InGame)))
+        .add_systems(OnEnter(AppState::InGame), (spawn_player, generate_jump_animation).chain())
+        .add_systems(Update, (move_player.before(integrate_motion), jump_player, change_color).run_if(in_state(RunState::Running)))
+        // Downed players leave once every system of the frame is done with them.
+        .add_systems(Last, player_death.run_if(in_state(RunState::Running)))
         ;
     }
 }
 
-#[derive(Event, Default)]
-pub struct PlayerHitEvent;
-
-fn spawn_player(
+pub fn spawn_player(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
+    roster: Res<Roster>,
 ) {
-    let player_jump = Name::new("player_jump");
-
-    commands.spawn((
-        MaterialMesh2dBundle {
-            mesh: meshes.add(Rectangle::default()).into(),
-            transform: Transform::default().with_scale(Vec3::splat(PLAYER_SIZE)),
-            material: materials.add(Color::from(PURPLE)),
-            ..default() 
-        },
-        Player::default(),
-        AnimationPlayer::default(),
-        player_jump,
-        Shape::Rectangle(Rectangle::from_length(PLAYER_SIZE)),
-        Health(PLAYER_LIFES),
-    ));
+    let count = roster.0.len();
+    for (index, pick) in roster.0.iter().enumerate() {
+        let character = pick.character;
+        let stats = character.stats();
+        let offset = (index as f32 - (count as f32 - 1.) / 2.) * PLAYER_SPACING;
+
+        commands.spawn((
+            MaterialMesh2dBundle {
+                mesh: meshes.add(character.mesh()).into(),
+                transform: Transform::from_xyz(offset, 0., 0.).with_scale(Vec3::splat(PLAYER_SIZE)),
+                material: materials.add(character.color()),
+                ..default() 
+            },
+            Player { index, ..default() },
+            pick.device,
+            AnimationPlayer::default(),
+            Name::new(format!("player_jump_{index}")),
+            character.shape(),
+            Health::new(stats.max_health()),
+            stats,
+            IFrames(IFRAME_TIME),
+            Critical { chance: character.crit_chance(), multiplier: 2 },
+            character.dash(),
+            character.arsenal(),
+            character,
+            Velocity::default(),
+            Acceleration::default(),
+        ));
+    }
 }
 
 fn generate_jump_animation(
     mut commands: Commands,
     mut animations: ResMut<Assets<AnimationClip>>,
-    mut player_name_query: Query<(&Name, Entity), With<Player>>,
+    player_name_query: Query<(&Name, Entity), With<Player>>,
     mut graphs: ResMut<Assets<AnimationGraph>>,
     mut player_animations: ResMut<PlayerAnimations>,
 ) {
-
-    let Ok((player_name, player_entity)) = player_name_query.get_single_mut() else {
+    // Each player is its own animation target, the shared clip holds one curve per player.
+    let targets: Vec<_> = player_name_query.iter().map(|(name, entity)| (AnimationTargetId::from_name(name), entity)).collect();
+    if targets.is_empty() {
         return;
+    }
+    let jump_curve = VariableCurve {
+        keyframe_timestamps: vec![0.0, JUMP_SPEED / 2., JUMP_SPEED, JUMP_SPEED + JUMP_SPEED / 2., 2. * JUMP_SPEED],
+        keyframes: Keyframes::Scale(vec![
+            Vec3::splat(PLAYER_SIZE),
+            Vec3::splat(PLAYER_SIZE + 0.25 * PLAYER_SIZE),
+            Vec3::splat(PLAYER_SIZE + 0.5 * PLAYER_SIZE),
+            Vec3::splat(PLAYER_SIZE + 0.25 * PLAYER_SIZE),
+            Vec3::splat(PLAYER_SIZE),
+        ]),
+        interpolation: Interpolation::Linear,
     };
     let mut animation = AnimationClip::default();
-    let jump_animation_target_id = AnimationTargetId::from_name(&player_name);
-
-    animation.add_curve_to_target(
-        jump_animation_target_id,
-        VariableCurve {
-            keyframe_timestamps: vec![0.0, JUMP_SPEED / 2., JUMP_SPEED, JUMP_SPEED + JUMP_SPEED / 2., 2. * JUMP_SPEED],
-            keyframes: Keyframes::Scale(vec![
-                Vec3::splat(PLAYER_SIZE),
-                Vec3::splat(PLAYER_SIZE + 0.25 * PLAYER_SIZE),
-                Vec3::splat(PLAYER_SIZE + 0.5 * PLAYER_SIZE),
-                Vec3::splat(PLAYER_SIZE + 0.25 * PLAYER_SIZE),
-                Vec3::splat(PLAYER_SIZE),
-            ]),
-            interpolation: Interpolation::Linear,
-        },
-    );
+    for &(target_id, _) in &targets {
+        animation.add_curve_to_target(target_id, jump_curve.clone());
+    }
 
     let animation_handle = animations.add(animation);
     let (graph, animation_index) = AnimationGraph::from_clip(animation_handle);    
-    commands.entity(player_entity).insert((
-        graphs.add(graph),
-        AnimationTarget {
-            id: jump_animation_target_id,
-            player: player_entity
-        }
-    ));
-    player_animations.0.push(animation_index);
-
+    let graph = graphs.add(graph);
+    for (target_id, player_entity) in targets {
+        commands.entity(player_entity).insert((
+            graph.clone(),
+            AnimationTarget {
+                id: target_id,
+                player: player_entity
+            }
+        ));
+    }
+    // Rebuilt every run, so the graph of the previous one is replaced rather than kept.
+    player_animations.0 = vec![animation_index];
 }
 
-fn move_player(
-    keyboard_input: Res<ButtonInput<KeyCode>>,
-    mut player_query: Query<&mut Transform, With<Player>>,
-    mut player_move_events: EventWriter<PlayerMoveEvent>,
-    time: Res<Time>
-) {
-    let Ok(mut player_transform) = player_query.get_single_mut() else {
-        return;
-    };
-
+/// Direction the player asks to move in, unit length for keys and scaled by deflection for sticks.
+pub fn movement_input(actions: &ButtonInput<InputAction>, analog: &AnalogInput) -> Vec3 {
     let mut direction = Vec3::ZERO;
 
-    if keyboard_input.pressed(KeyCode::KeyS) {
+    if actions.pressed(InputAction::MoveDown) {
         direction.y -= 1.;
     }
 
-    if keyboard_input.pressed(KeyCode::KeyW) {
+    if actions.pressed(InputAction::MoveUp) {
         direction.y += 1.;
     }
 
-    if keyboard_input.pressed(KeyCode::KeyA) {
+    if actions.pressed(InputAction::MoveLeft) {
         direction.x -= 1.;
     }
 
-    if keyboard_input.pressed(KeyCode::KeyD) {
+    if actions.pressed(InputAction::MoveRight) {
         direction.x += 1.;
     }
 
-    let movement = direction.normalize_or_zero() * time.delta_seconds() * SPEED;
+    // The stick keeps its deflection so it can walk slowly, keys always move at full speed.
+    if direction == Vec3::ZERO { analog.movement.extend(0.) } else { direction.normalize_or_zero() }
+}
 
-    player_transform.translation += movement;
-    if movement != Vec3::ZERO {
-        player_move_events.send(PlayerMoveEvent(direction));
+pub fn move_player(
+    inputs: Res<DeviceInputs>,
+    mut player_query: Query<PlayerMover, With<Player>>,
+    mut player_move_events: EventWriter<PlayerMoveEvent>,
+    time: Res<Time>
+) {
+    let delta = time.delta_seconds();
+    if delta == 0. {
+        return;
+    }
+
+    for (player_entity, device, velocity, mut acceleration, stats, status, dash) in &mut player_query {
+        if dash.is_dashing(time.elapsed_seconds()) {
+            continue;
+        }
+
+        let input = inputs.get(*device);
+        let direction = movement_input(&input.actions, &input.analog);
+        let speed = stats.get(Stat::MoveSpeed) * status.map_or(1., StatusEffects::speed_multiplier);
+        let target = direction.truncate() * speed;
+        let rate = if target == Vec2::ZERO { DECELERATION } else { ACCELERATION };
+
+        // Clamped so the velocity settles on the target instead of overshooting it.
+        acceleration.0 += ((target - velocity.0) / delta).clamp_length_max(rate);
+        if target != Vec2::ZERO {
+            player_move_events.send(PlayerMoveEvent { player: player_entity, direction });
+        }
     }
 }
     
 fn jump_player(
-    keyboard_input: Res<ButtonInput<KeyCode>>,
-    mut player_query: Query<(&mut AnimationPlayer, &mut Player)>,
+    inputs: Res<DeviceInputs>,
+    mut player_query: Query<(Entity, &InputDevice, &mut AnimationPlayer, &mut Player)>,
     player_animations: Res<PlayerAnimations>,
     mut player_jump_start_events: EventWriter<PlayerJumpStartEvent>,
     mut player_jump_end_events: EventWriter<PlayerJumpEndEvent>
 ) {
-    let Ok((mut player_animation_player, mut player)) = player_query.get_single_mut() else {
-        return;
-    };
-
     let Some(jump_animation) = player_animations.0.get(0) else {
         return;
     };
 
-    if keyboard_input.just_pressed(KeyCode::Space) {
-        if !player_animation_player.is_playing_animation(*jump_animation) {
-            player_jump_start_events.send_default();
-            player_animation_player.play(*jump_animation);
+    for (player_entity, device, mut player_animation_player, mut player) in &mut player_query {
+        if inputs.get(*device).actions.just_pressed(InputAction::Jump) {
+            if !player_animation_player.is_playing_animation(*jump_animation) {
+                player_jump_start_events.send(PlayerJumpStartEvent(player_entity));
+                player_animation_player.play(*jump_animation);
+            }
+            player.is_jumping = true;
+        }
+        if player_animation_player.animation(*jump_animation).is_some() 
+            && player_animation_player.all_finished() {
+                player_jump_end_events.send(PlayerJumpEndEvent(player_entity));
+                player_animation_player.stop_all();
+                player.is_jumping = false;
         }
-        player.is_jumping = true;
-    }
-    if player_animation_player.animation(*jump_animation).is_some() 
-        && player_animation_player.all_finished() {
-            player_jump_end_events.send_default();
-            player_animation_player.stop_all();
-            player.is_jumping = false;
     }
 }
 
 pub fn change_color(
     mut player_jump_start_events: EventReader<PlayerJumpStartEvent>,
     mut player_jump_end_events: EventReader<PlayerJumpEndEvent>,
-    player_query: Query<&Handle<ColorMaterial>, With<Player>>,
+    player_query: Query<(&Handle<ColorMaterial>, &Character), With<Player>>,
     mut materials: ResMut<Assets<ColorMaterial>>,
 ) {
-    let Ok(player_material_handle) = player_query.get_single() else {
-        return;
-    };
-
-    let Some(player_material) = materials.get_mut(player_material_handle) else {
-        return;
-    };
-
-    for _ in player_jump_start_events.read() {
-        player_material.color = Color::from(YELLOW);
+    for event in player_jump_start_events.read() {
+        let Ok((player_material_handle, _)) = player_query.get(event.0) else {
+            continue;
+        };
+        if let Some(player_material) = materials.get_mut(player_material_handle) {
+            player_material.color = Color::from(YELLOW);
+        }
     }
 
-    for _ in player_jump_end_events.read() {
-        player_material.color = Color::from(PURPLE);
+    for event in player_jump_end_events.read() {
+        let Ok((player_material_handle, character)) = player_query.get(event.0) else {
+            continue;
+        };
+        if let Some(player_material) = materials.get_mut(player_material_handle) {
+            player_material.color = character.color();
+        }
     }
 }
 
+/// Removes players whose health ran out, the run ends when nobody is left.
 pub fn player_death(
+    mut commands: Commands,
     mut next_state: ResMut<NextState<AppState>>,
-    mut player_query: Query<&Health, With<Player>>,
+    player_query: Query<(Entity, &Health), With<Player>>,
 ) {
-    let Ok(player_health) = player_query.get_single_mut() else {
-        return;
-    };
+    let mut alive = 0;
+    for (player_entity, player_health) in &player_query {
+        if player_health.is_dead() {
+            commands.entity(player_entity).despawn_recursive();
+        } else {
+            alive += 1;
+        }
+    }
 
-    if player_health.0 <= 0 {
+    if alive == 0 {
         next_state.set(AppState::EndGame)
     }
-}
\ No newline at end of file
+}
+
+/// Entity and position of the living player closest to `position`.
+pub fn nearest_player(players: &[(Entity, Vec2)], position: Vec2) -> Option<(Entity, Vec2)> {
+    players.iter()
+        .copied()
+        .min_by(|(_, a), (_, b)| a.distance_squared(position).total_cmp(&b.distance_squared(position)))
+}
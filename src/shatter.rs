@@ -0,0 +1,146 @@
+use std::f32::consts::{PI, TAU};
+
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::{bounding::Shape, enemies::{Death, Enemy}, AppState, RunState};
+
+const SHATTER_TIME: f32 = 0.6;
+const SHATTER_SPEED: f32 = 120.;
+const SHATTER_SPIN: f32 = 8.;
+const ELLIPSE_WEDGES: u32 = 6;
+/// Upper bound on live shatter effects, deaths beyond it simply vanish.
+const MAX_SHATTERS: usize = 48;
+
+/// Enemies that started dying this frame.
+type JustDied = (With<Enemy>, Added<Death>);
+
+pub struct ShatterPlugin;
+
+impl Plugin for ShatterPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(Update, (spawn_shatters, update_shatters).run_if(in_state(RunState::Running)))
+            .add_systems(OnExit(AppState::InGame), despawn_shatters)
+        ;
+    }
+}
+
+/// Parent of one enemy's fragments, which share a material so they fade together.
+#[derive(Component)]
+struct Shatter {
+    born: f32,
+    material: Handle<ColorMaterial>,
+}
+
+#[derive(Component)]
+struct Fragment {
+    velocity: Vec2,
+    spin: f32,
+}
+
+/// Splits a shape into congruent pieces: one triangle centred on its centroid,
+/// plus the offset and rotation of every piece within the shape.
+fn fragments(shape: &Shape) -> Option<(Triangle2d, Vec<(Vec2, f32)>)> {
+    match shape {
+        Shape::Triangle(triangle) => {
+            // Midpoint subdivision: three half-size corners and the flipped middle.
+            let centroid = triangle.vertices.iter().sum::<Vec2>() / 3.;
+            let [a, b, c] = triangle.vertices.map(|vertex| (vertex - centroid) / 2.);
+            let mut pieces: Vec<_> = triangle.vertices.iter().map(|&vertex| ((vertex + centroid) / 2., 0.)).collect();
+            pieces.push((centroid, PI));
+            Some((Triangle2d::new(a, b, c), pieces))
+        }
+        Shape::Ellipse(ellipse) => {
+            let angle = TAU / ELLIPSE_WEDGES as f32;
+            let radius = ellipse.half_size.max_element();
+            let left = Vec2::from_angle(-angle / 2.) * radius;
+            let right = Vec2::from_angle(angle / 2.) * radius;
+            let centroid = (left + right) / 3.;
+            let pieces = (0..ELLIPSE_WEDGES)
+                .map(|wedge| {
+                    let rotation = wedge as f32 * angle;
+                    (Vec2::from_angle(rotation).rotate(centroid), rotation)
+                })
+                .collect();
+            Some((Triangle2d::new(-centroid, left - centroid, right - centroid), pieces))
+        }
+        Shape::Rectangle(_) | Shape::Line(_) => None,
+    }
+}
+
+fn spawn_shatters(
+    mut commands: Commands,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    dying_query: Query<(&Transform, &Shape, &Handle<ColorMaterial>), JustDied>,
+    shatters_query: Query<(), With<Shatter>>,
+    time: Res<Time>,
+) {
+    let mut live = shatters_query.iter().count();
+    let mut rng = rand::thread_rng();
+
+    for (transform, shape, material_handle) in &dying_query {
+        if live >= MAX_SHATTERS {
+            break;
+        }
+        let Some((piece, pieces)) = fragments(shape) else {
+            continue;
+        };
+        live += 1;
+
+        let color = materials.get(material_handle).map_or(Color::WHITE, |material| material.color);
+        let material = materials.add(color);
+        let mesh = meshes.add(piece);
+
+        commands.spawn((SpatialBundle::from_transform(*transform), Shatter {
+            born: time.elapsed_seconds(),
+            material: material.clone(),
+        }))
+        .with_children(|parent| {
+            for (offset, rotation) in pieces {
+                let scatter = Vec2::from_angle(rng.gen_range(0.0..TAU)) * 0.3;
+                parent.spawn((ColorMesh2dBundle {
+                    mesh: mesh.clone().into(),
+                    material: material.clone(),
+                    transform: Transform::from_translation(offset.extend(0.)).with_rotation(Quat::from_rotation_z(rotation)),
+                    ..default()
+                }, Fragment {
+                    velocity: (offset.normalize_or_zero() + scatter) * SHATTER_SPEED * rng.gen_range(0.6..1.4),
+                    spin: rng.gen_range(-SHATTER_SPIN..SHATTER_SPIN),
+                }));
+            }
+        });
+    }
+}
+
+fn update_shatters(
+    mut commands: Commands,
+    shatters_query: Query<(Entity, &Shatter)>,
+    mut fragments_query: Query<(&mut Transform, &Fragment)>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+    time: Res<Time>,
+) {
+    for (mut transform, fragment) in &mut fragments_query {
+        transform.translation += fragment.velocity.extend(0.) * time.delta_seconds();
+        transform.rotate_z(fragment.spin * time.delta_seconds());
+    }
+
+    for (entity, shatter) in &shatters_query {
+        let age = (time.elapsed_seconds() - shatter.born) / SHATTER_TIME;
+        if age >= 1. {
+            commands.entity(entity).despawn_recursive();
+        } else if let Some(material) = materials.get_mut(&shatter.material) {
+            material.color.set_alpha(1. - age);
+        }
+    }
+}
+
+fn despawn_shatters(
+    mut commands: Commands,
+    shatters_query: Query<Entity, With<Shatter>>,
+) {
+    for entity in &shatters_query {
+        commands.entity(entity).despawn_recursive();
+    }
+}
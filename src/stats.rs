@@ -0,0 +1,127 @@
+use bevy::{prelude::*, utils::HashMap};
+
+use crate::{enemies::{Death, Health}, RunState};
+
+/// Living entities whose stats changed this frame.
+type StatsChanged = (Changed<Stats>, Without<Death>);
+
+pub struct StatsPlugin;
+
+impl Plugin for StatsPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_systems(Update, (sync_max_health, regenerate).run_if(in_state(RunState::Running)))
+        ;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Stat {
+    MoveSpeed,
+    MaxHealth,
+    /// Health restored per second.
+    Regeneration,
+    /// Shots per second.
+    FireRate,
+    Damage,
+    ProjectileSpeed,
+    /// Distance at which pickups start flying to the player.
+    PickupRadius,
+    /// Multiplier on loot drop chances.
+    Luck,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum Modifier {
+    /// Added to the base value.
+    Add(f32),
+    /// Fraction added to the multiplier, 0.2 for +20%.
+    Multiply(f32),
+}
+
+/// Base values and the modifiers stacked on top of them.
+#[derive(Component, Debug, Clone)]
+pub struct Stats {
+    base: HashMap<Stat, f32>,
+    modifiers: Vec<(Stat, Modifier)>,
+    /// Regeneration below one full point of health, carried between frames.
+    regen_progress: f32,
+}
+
+impl Default for Stats {
+    fn default() -> Self {
+        Self {
+            base: HashMap::from([
+                (Stat::MoveSpeed, 200.),
+                (Stat::MaxHealth, 3.),
+                (Stat::Regeneration, 0.),
+                (Stat::FireRate, 20.),
+                (Stat::Damage, 1.),
+                (Stat::ProjectileSpeed, 300.),
+                (Stat::PickupRadius, 150.),
+                (Stat::Luck, 1.),
+            ]),
+            modifiers: Vec::new(),
+            regen_progress: 0.,
+        }
+    }
+}
+
+impl Stats {
+    /// Replaces the base value of `stat`, for characters that start stronger or weaker in it.
+    pub fn with_base(mut self, stat: Stat, value: f32) -> Self {
+        self.base.insert(stat, value);
+        self
+    }
+
+    /// Final value: base plus flat bonuses, times one plus every multiplier.
+    pub fn get(&self, stat: Stat) -> f32 {
+        let (add, multiply) = self.modifiers.iter()
+            .filter(|(modified, _)| *modified == stat)
+            .fold((0., 0.), |(add, multiply), (_, modifier)| match modifier {
+                Modifier::Add(value) => (add + value, multiply),
+                Modifier::Multiply(value) => (add, multiply + value),
+            });
+        (self.base.get(&stat).copied().unwrap_or(0.) + add) * (1. + multiply).max(0.)
+    }
+
+    pub fn add_modifier(&mut self, stat: Stat, modifier: Modifier) {
+        self.modifiers.push((stat, modifier));
+    }
+
+    /// Health the entity spawns with and is capped at.
+    pub fn max_health(&self) -> i32 {
+        self.get(Stat::MaxHealth).round() as i32
+    }
+}
+
+fn sync_max_health(mut stats_query: Query<(&Stats, &mut Health), StatsChanged>) {
+    for (stats, mut health) in &mut stats_query {
+        if health.max() != stats.max_health() {
+            health.set_max(stats.max_health());
+        }
+    }
+}
+
+fn regenerate(
+    mut stats_query: Query<(&mut Stats, &mut Health), Without<Death>>,
+    time: Res<Time>,
+) {
+    for (mut stats, mut health) in &mut stats_query {
+        let rate = stats.get(Stat::Regeneration);
+        if rate <= 0. {
+            continue;
+        }
+        if health.current() >= health.max() {
+            stats.regen_progress = 0.;
+            continue;
+        }
+
+        stats.regen_progress += rate * time.delta_seconds();
+        let whole = stats.regen_progress.floor();
+        if whole >= 1. {
+            stats.regen_progress -= whole;
+            health.heal(whole as i32);
+        }
+    }
+}
@@ -0,0 +1,197 @@
+use bevy::{color::palettes::css::{LIGHT_CYAN, LIME, ORANGE_RED, PLUM, SKY_BLUE}, prelude::*};
+
+use crate::{damage::{DamageEvent, DamageType}, enemies::{Death, Health}, RunState};
+
+const TINT_STRENGTH: f32 = 0.6;
+
+/// Living entities that damage over time can still hurt.
+type Afflicted = (With<Health>, Without<Death>);
+
+pub struct StatusPlugin;
+
+impl Plugin for StatusPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .add_event::<ApplyStatusEvent>()
+            .add_systems(Update, (apply_status_events, tick_status_effects, tint_affected).chain().run_if(in_state(RunState::Running)))
+        ;
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatusKind {
+    Burn,
+    Poison,
+    Slow,
+    Freeze,
+    Stun,
+}
+
+impl StatusKind {
+    fn max_stacks(self) -> u32 {
+        match self {
+            StatusKind::Burn => 3,
+            StatusKind::Poison => 5,
+            StatusKind::Slow => 3,
+            StatusKind::Freeze | StatusKind::Stun => 1,
+        }
+    }
+
+    /// Seconds between damage ticks, for the kinds that deal damage over time.
+    fn tick_rate(self) -> Option<f32> {
+        match self {
+            StatusKind::Burn => Some(0.5),
+            StatusKind::Poison => Some(1.),
+            StatusKind::Slow | StatusKind::Freeze | StatusKind::Stun => None,
+        }
+    }
+
+    fn damage_type(self) -> Option<DamageType> {
+        match self {
+            StatusKind::Burn => Some(DamageType::Fire),
+            StatusKind::Poison => Some(DamageType::Poison),
+            StatusKind::Slow | StatusKind::Freeze | StatusKind::Stun => None,
+        }
+    }
+
+    fn tint(self) -> Srgba {
+        match self {
+            StatusKind::Burn => ORANGE_RED,
+            StatusKind::Poison => LIME,
+            StatusKind::Slow => PLUM,
+            StatusKind::Freeze => SKY_BLUE,
+            StatusKind::Stun => LIGHT_CYAN,
+        }
+    }
+}
+
+/// Asks for `kind` to be applied to `target` for `duration` seconds, adding one stack.
+#[derive(Event)]
+pub struct ApplyStatusEvent {
+    pub target: Entity,
+    pub kind: StatusKind,
+    pub duration: f32,
+}
+
+#[derive(Debug)]
+pub struct StatusEffect {
+    pub kind: StatusKind,
+    pub stacks: u32,
+    remaining: f32,
+    tick: Option<Timer>,
+}
+
+/// Timed effects on an entity, at most one entry per kind.
+#[derive(Component, Debug, Default)]
+pub struct StatusEffects {
+    effects: Vec<StatusEffect>,
+    /// Color the owner last gave the material, restored once every effect has worn off.
+    base_color: Option<Color>,
+    /// Color written by the last tint, anything else found in the material was set by the owner since.
+    tinted: Option<Color>,
+}
+
+impl StatusEffects {
+    /// Adds a stack and refreshes the duration, or starts a new effect.
+    pub fn apply(&mut self, kind: StatusKind, duration: f32) {
+        if let Some(effect) = self.effects.iter_mut().find(|effect| effect.kind == kind) {
+            effect.stacks = (effect.stacks + 1).min(kind.max_stacks());
+            effect.remaining = effect.remaining.max(duration);
+            return;
+        }
+
+        self.effects.push(StatusEffect {
+            kind,
+            stacks: 1,
+            remaining: duration,
+            tick: kind.tick_rate().map(|rate| Timer::from_seconds(rate, TimerMode::Repeating)),
+        });
+    }
+
+    pub fn has(&self, kind: StatusKind) -> bool {
+        self.effects.iter().any(|effect| effect.kind == kind)
+    }
+
+    /// Factor applied to movement: zero while frozen or stunned, reduced per slow stack.
+    pub fn speed_multiplier(&self) -> f32 {
+        if self.has(StatusKind::Freeze) || self.has(StatusKind::Stun) {
+            return 0.;
+        }
+        self.effects.iter()
+            .find(|effect| effect.kind == StatusKind::Slow)
+            .map_or(1., |slow| (1. - 0.25 * slow.stacks as f32).max(0.25))
+    }
+}
+
+fn apply_status_events(
+    mut commands: Commands,
+    mut events: EventReader<ApplyStatusEvent>,
+    mut affected_query: Query<&mut StatusEffects>,
+) {
+    for event in events.read() {
+        if let Ok(mut effects) = affected_query.get_mut(event.target) {
+            effects.apply(event.kind, event.duration);
+        } else if let Some(mut target) = commands.get_entity(event.target) {
+            let mut effects = StatusEffects::default();
+            effects.apply(event.kind, event.duration);
+            target.insert(effects);
+        }
+    }
+}
+
+fn tick_status_effects(
+    mut affected_query: Query<(Entity, &mut StatusEffects), Afflicted>,
+    mut damage_events: EventWriter<DamageEvent>,
+    time: Res<Time>,
+) {
+    for (entity, mut status) in &mut affected_query {
+        for effect in &mut status.effects {
+            effect.remaining -= time.delta_seconds();
+            let Some(kind) = effect.kind.damage_type() else {
+                continue;
+            };
+            if effect.tick.as_mut().is_some_and(|tick| tick.tick(time.delta()).just_finished()) {
+                damage_events.send(DamageEvent {
+                    target: entity,
+                    source: None,
+                    amount: effect.stacks as i32,
+                    kind,
+                    on_hit: None,
+                    over_time: true,
+                });
+            }
+        }
+        if status.effects.iter().any(|effect| effect.remaining <= 0.) {
+            status.effects.retain(|effect| effect.remaining > 0.);
+        }
+    }
+}
+
+fn tint_affected(
+    mut commands: Commands,
+    mut affected_query: Query<(Entity, &mut StatusEffects, &Handle<ColorMaterial>)>,
+    mut materials: ResMut<Assets<ColorMaterial>>,
+) {
+    for (entity, mut status, material_handle) in &mut affected_query {
+        let Some(material) = materials.get_mut(material_handle) else {
+            continue;
+        };
+        // Boss phases and jumps recolor the material while tinted, so follow their latest color.
+        if status.tinted != Some(material.color) {
+            status.base_color = Some(material.color);
+        }
+        let base_color = status.base_color.unwrap_or(material.color);
+
+        // The most recently applied effect decides the tint.
+        match status.effects.last() {
+            Some(effect) => {
+                material.color = base_color.mix(&Color::from(effect.kind.tint()), TINT_STRENGTH);
+                status.tinted = Some(material.color);
+            }
+            None => {
+                material.color = base_color;
+                commands.entity(entity).remove::<StatusEffects>();
+            }
+        }
+    }
+}
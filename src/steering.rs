@@ -0,0 +1,233 @@
+use bevy::{math::bounding::{Aabb2d, BoundingVolume}, prelude::*, utils::HashMap};
+use rand::Rng;
+
+use crate::{enemies::LiveEnemy, RunState};
+
+const CELL_SIZE: f32 = 64.;
+const NEIGHBOUR_RADIUS: f32 = 80.;
+const SEPARATION_RADIUS: f32 = 40.;
+const SLOWING_RADIUS: f32 = 100.;
+const AVOID_DISTANCE: f32 = 80.;
+const WANDER_DISTANCE: f32 = 40.;
+const WANDER_RADIUS: f32 = 20.;
+const WANDER_JITTER: f32 = 0.5;
+
+pub struct SteeringPlugin;
+
+impl Plugin for SteeringPlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<SpatialGrid>()
+            .add_systems(Update, update_spatial_grid.run_if(in_state(RunState::Running)))
+        ;
+    }
+}
+
+#[derive(Clone, Copy)]
+pub struct SteeringWeights {
+    pub seek: f32,
+    pub arrive: f32,
+    pub separation: f32,
+    pub alignment: f32,
+    pub cohesion: f32,
+    pub wander: f32,
+    pub avoidance: f32,
+}
+
+impl Default for SteeringWeights {
+    fn default() -> Self {
+        Self {
+            seek: 1.,
+            arrive: 0.,
+            separation: 1.5,
+            alignment: 0.2,
+            cohesion: 0.1,
+            wander: 0.3,
+            avoidance: 2.,
+        }
+    }
+}
+
+#[derive(Component)]
+pub struct Steering {
+    pub velocity: Vec2,
+    pub max_speed: f32,
+    pub max_force: f32,
+    pub weights: SteeringWeights,
+    wander_angle: f32,
+}
+
+impl Steering {
+    pub fn new(max_speed: f32) -> Self {
+        Self {
+            velocity: Vec2::ZERO,
+            max_speed,
+            max_force: max_speed * 2.,
+            weights: SteeringWeights::default(),
+            wander_angle: 0.,
+        }
+    }
+
+    /// Sums every weighted force toward `target` and integrates the velocity.
+    pub fn steer(
+        &mut self,
+        entity: Entity,
+        position: Vec2,
+        target: Vec2,
+        grid: &SpatialGrid,
+        obstacles: &[Aabb2d],
+        delta: f32,
+    ) -> Vec2 {
+        let neighbours: Vec<&Neighbour> = grid
+            .neighbours(position, NEIGHBOUR_RADIUS)
+            .filter(|neighbour| neighbour.entity != entity)
+            .collect();
+
+        let weights = self.weights;
+        let mut force = Vec2::ZERO;
+        if weights.seek != 0. {
+            force += seek(position, target, self.velocity, self.max_speed) * weights.seek;
+        }
+        if weights.arrive != 0. {
+            force += arrive(position, target, self.velocity, self.max_speed) * weights.arrive;
+        }
+        if weights.separation != 0. {
+            force += separation(position, &neighbours, self.velocity, self.max_speed) * weights.separation;
+        }
+        if weights.alignment != 0. {
+            force += alignment(&neighbours, self.velocity) * weights.alignment;
+        }
+        if weights.cohesion != 0. {
+            force += cohesion(position, &neighbours, self.velocity, self.max_speed) * weights.cohesion;
+        }
+        if weights.wander != 0. {
+            force += self.wander() * weights.wander;
+        }
+        if weights.avoidance != 0. {
+            force += avoid_obstacles(position, self.velocity, self.max_speed, obstacles) * weights.avoidance;
+        }
+
+        self.velocity = (self.velocity + force.clamp_length_max(self.max_force) * delta)
+            .clamp_length_max(self.max_speed);
+        self.velocity
+    }
+
+    fn wander(&mut self) -> Vec2 {
+        self.wander_angle += rand::thread_rng().gen_range(-WANDER_JITTER..WANDER_JITTER);
+        let heading = self.velocity.try_normalize().unwrap_or(Vec2::Y);
+        heading * WANDER_DISTANCE + Vec2::from_angle(self.wander_angle) * WANDER_RADIUS
+    }
+}
+
+pub struct Neighbour {
+    pub entity: Entity,
+    pub position: Vec2,
+    pub velocity: Vec2,
+}
+
+/// Buckets steering entities by cell so neighbour lookups only visit nearby cells.
+#[derive(Resource, Default)]
+pub struct SpatialGrid {
+    cells: HashMap<IVec2, Vec<Neighbour>>,
+}
+
+impl SpatialGrid {
+    fn cell(position: Vec2) -> IVec2 {
+        (position / CELL_SIZE).floor().as_ivec2()
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.values_mut().for_each(Vec::clear);
+    }
+
+    pub fn insert(&mut self, neighbour: Neighbour) {
+        self.cells.entry(Self::cell(neighbour.position)).or_default().push(neighbour);
+    }
+
+    pub fn neighbours(&self, position: Vec2, radius: f32) -> impl Iterator<Item = &Neighbour> {
+        let min = Self::cell(position - Vec2::splat(radius));
+        let max = Self::cell(position + Vec2::splat(radius));
+        (min.x..=max.x)
+            .flat_map(move |x| (min.y..=max.y).map(move |y| IVec2::new(x, y)))
+            .filter_map(|cell| self.cells.get(&cell))
+            .flatten()
+            .filter(move |neighbour| neighbour.position.distance_squared(position) <= radius * radius)
+    }
+}
+
+pub fn seek(position: Vec2, target: Vec2, velocity: Vec2, max_speed: f32) -> Vec2 {
+    (target - position).normalize_or_zero() * max_speed - velocity
+}
+
+pub fn arrive(position: Vec2, target: Vec2, velocity: Vec2, max_speed: f32) -> Vec2 {
+    let offset = target - position;
+    let distance = offset.length();
+    let speed = max_speed * (distance / SLOWING_RADIUS).min(1.);
+    offset.normalize_or_zero() * speed - velocity
+}
+
+pub fn separation(position: Vec2, neighbours: &[&Neighbour], velocity: Vec2, max_speed: f32) -> Vec2 {
+    let mut push = Vec2::ZERO;
+    for neighbour in neighbours {
+        let offset = position - neighbour.position;
+        let distance_squared = offset.length_squared();
+        if distance_squared > 0. && distance_squared < SEPARATION_RADIUS * SEPARATION_RADIUS {
+            push += offset / distance_squared;
+        }
+    }
+    if push == Vec2::ZERO {
+        return Vec2::ZERO;
+    }
+    push.normalize() * max_speed - velocity
+}
+
+pub fn alignment(neighbours: &[&Neighbour], velocity: Vec2) -> Vec2 {
+    if neighbours.is_empty() {
+        return Vec2::ZERO;
+    }
+    let average = neighbours.iter().map(|neighbour| neighbour.velocity).sum::<Vec2>() / neighbours.len() as f32;
+    average - velocity
+}
+
+pub fn cohesion(position: Vec2, neighbours: &[&Neighbour], velocity: Vec2, max_speed: f32) -> Vec2 {
+    if neighbours.is_empty() {
+        return Vec2::ZERO;
+    }
+    let center = neighbours.iter().map(|neighbour| neighbour.position).sum::<Vec2>() / neighbours.len() as f32;
+    seek(position, center, velocity, max_speed)
+}
+
+/// Pushes away from the closest point of any obstacle lying ahead along the velocity.
+pub fn avoid_obstacles(position: Vec2, velocity: Vec2, max_speed: f32, obstacles: &[Aabb2d]) -> Vec2 {
+    let Some(heading) = velocity.try_normalize() else {
+        return Vec2::ZERO;
+    };
+    let ahead = position + heading * AVOID_DISTANCE * velocity.length() / max_speed;
+
+    let mut force = Vec2::ZERO;
+    for obstacle in obstacles {
+        for probe in [ahead, (position + ahead) / 2.] {
+            let closest = obstacle.closest_point(probe);
+            if closest == probe {
+                let away = (probe - obstacle.center()).normalize_or_zero();
+                force += away * max_speed;
+                break;
+            }
+        }
+    }
+    force
+}
+
+pub fn update_spatial_grid(
+    mut grid: ResMut<SpatialGrid>,
+    steering_query: Query<(Entity, &Transform, &Steering), LiveEnemy>,
+) {
+    grid.clear();
+    for (entity, transform, steering) in &steering_query {
+        grid.insert(Neighbour {
+            entity,
+            position: transform.translation.xy(),
+            velocity: steering.velocity,
+        });
+    }
+}
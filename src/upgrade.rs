@@ -0,0 +1,334 @@
+use bevy::{color::palettes::css::{GOLD, LIGHT_GRAY, MEDIUM_PURPLE, SKY_BLUE}, prelude::*};
+use rand::seq::SliceRandom;
+
+use crate::{bullet::{Arsenal, NOVA_MAX_LEVEL}, dash::Dash, enemies::EnemyKilledEvent, input::{pause_time, resume_time, toggle_pause, InputAction}, player::Player, stats::{Modifier, Stat, Stats}, AppState, RunState};
+
+const CHOICES: usize = 3;
+const MAX_DASH_CHARGES: u32 = 3;
+const MIN_DASH_COOLDOWN: f32 = 0.5;
+const MAX_MULTISHOT: u32 = 4;
+
+const NORMAL_BUTTON: Color = Color::srgb(0.15, 0.15, 0.15);
+const FOCUSED_BUTTON: Color = Color::srgb(0.3, 0.3, 0.3);
+
+pub struct UpgradePlugin;
+
+impl Plugin for UpgradePlugin {
+    fn build(&self, app: &mut App) {
+        app
+            .init_resource::<Experience>()
+            .add_systems(OnEnter(AppState::InGame), reset_experience)
+            .add_systems(Update, (
+                gain_experience,
+                open_level_up.run_if(not(resource_exists::<LevelUpScreen>)),
+                choose_upgrade.run_if(resource_exists::<LevelUpScreen>),
+            ).chain().after(toggle_pause).run_if(in_state(RunState::Running).or_else(in_state(RunState::LevelUp))))
+            .add_systems(OnEnter(RunState::LevelUp), pause_time)
+            .add_systems(OnExit(RunState::LevelUp), (resume_time, close_level_up))
+        ;
+    }
+}
+
+/// Experience and level of the current run.
+#[derive(Resource, Debug)]
+pub struct Experience {
+    pub level: u32,
+    pub xp: u32,
+    /// Level-ups whose upgrade has not been chosen yet.
+    pending: u32,
+}
+
+impl Default for Experience {
+    fn default() -> Self {
+        Self { level: 1, xp: 0, pending: 0 }
+    }
+}
+
+impl Experience {
+    /// Experience needed to go from the current level to the next.
+    pub fn to_next(&self) -> u32 {
+        5 + 5 * self.level
+    }
+
+    /// Adds experience, queueing an upgrade choice for every level it completes.
+    pub fn gain(&mut self, amount: u32) {
+        self.xp += amount;
+        while self.xp >= self.to_next() {
+            self.xp -= self.to_next();
+            self.level += 1;
+            self.pending += 1;
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Rarity {
+    Common,
+    Uncommon,
+    Rare,
+    Epic,
+}
+
+impl Rarity {
+    fn weight(self) -> f32 {
+        match self {
+            Rarity::Common => 60.,
+            Rarity::Uncommon => 25.,
+            Rarity::Rare => 12.,
+            Rarity::Epic => 3.,
+        }
+    }
+
+    fn color(self) -> Color {
+        Color::from(match self {
+            Rarity::Common => LIGHT_GRAY,
+            Rarity::Uncommon => SKY_BLUE,
+            Rarity::Rare => MEDIUM_PURPLE,
+            Rarity::Epic => GOLD,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Effect {
+    Stat(Stat, Modifier),
+    DashCharge,
+    DashCooldown,
+    Multishot,
+    Nova,
+    /// Evolution of a maxed nova.
+    Supernova,
+}
+
+struct Upgrade {
+    name: &'static str,
+    description: &'static str,
+    rarity: Rarity,
+    effect: Effect,
+}
+
+const UPGRADES: [Upgrade; 13] = [
+    Upgrade { name: "Swift Boots", description: "+10% move speed", rarity: Rarity::Common, effect: Effect::Stat(Stat::MoveSpeed, Modifier::Multiply(0.1)) },
+    Upgrade { name: "Vitality", description: "+1 max health", rarity: Rarity::Common, effect: Effect::Stat(Stat::MaxHealth, Modifier::Add(1.)) },
+    Upgrade { name: "Mending", description: "Regenerate 1 health every 10s", rarity: Rarity::Uncommon, effect: Effect::Stat(Stat::Regeneration, Modifier::Add(0.1)) },
+    Upgrade { name: "Trigger Finger", description: "+15% fire rate", rarity: Rarity::Common, effect: Effect::Stat(Stat::FireRate, Modifier::Multiply(0.15)) },
+    Upgrade { name: "Heavy Rounds", description: "+1 damage", rarity: Rarity::Rare, effect: Effect::Stat(Stat::Damage, Modifier::Add(1.)) },
+    Upgrade { name: "Velocity", description: "+20% projectile speed", rarity: Rarity::Common, effect: Effect::Stat(Stat::ProjectileSpeed, Modifier::Multiply(0.2)) },
+    Upgrade { name: "Magnet", description: "+30% pickup radius", rarity: Rarity::Common, effect: Effect::Stat(Stat::PickupRadius, Modifier::Multiply(0.3)) },
+    Upgrade { name: "Four-Leaf Clover", description: "+20% luck", rarity: Rarity::Uncommon, effect: Effect::Stat(Stat::Luck, Modifier::Multiply(0.2)) },
+    Upgrade { name: "Second Wind", description: "+1 dash charge", rarity: Rarity::Rare, effect: Effect::DashCharge },
+    Upgrade { name: "Quick Step", description: "-20% dash cooldown", rarity: Rarity::Uncommon, effect: Effect::DashCooldown },
+    Upgrade { name: "Split Shot", description: "+1 bullet per shot", rarity: Rarity::Rare, effect: Effect::Multishot },
+    Upgrade { name: "Nova", description: "Fires a ring of bullets", rarity: Rarity::Rare, effect: Effect::Nova },
+    Upgrade { name: "Supernova", description: "Evolves Nova: denser, faster, burning", rarity: Rarity::Epic, effect: Effect::Supernova },
+];
+
+impl Upgrade {
+    fn available(&self, dash: &Dash, arsenal: &Arsenal) -> bool {
+        match self.effect {
+            Effect::Stat(..) => true,
+            Effect::DashCharge => dash.max_charges < MAX_DASH_CHARGES,
+            Effect::DashCooldown => dash.cooldown * 0.8 >= MIN_DASH_COOLDOWN,
+            Effect::Multishot => arsenal.multishot < MAX_MULTISHOT,
+            Effect::Nova => arsenal.nova_level() < NOVA_MAX_LEVEL && !arsenal.has_supernova(),
+            Effect::Supernova => arsenal.nova_level() == NOVA_MAX_LEVEL && !arsenal.has_supernova(),
+        }
+    }
+
+    fn apply(&self, stats: &mut Stats, dash: &mut Dash, arsenal: &mut Arsenal) {
+        match self.effect {
+            Effect::Stat(stat, modifier) => stats.add_modifier(stat, modifier),
+            Effect::DashCharge => dash.max_charges += 1,
+            Effect::DashCooldown => dash.cooldown *= 0.8,
+            Effect::Multishot => arsenal.multishot += 1,
+            Effect::Nova => arsenal.level_nova(),
+            Effect::Supernova => arsenal.evolve_nova(),
+        }
+    }
+}
+
+/// Open choice screen, shown while the run is in `RunState::LevelUp`.
+#[derive(Resource)]
+struct LevelUpScreen {
+    root: Entity,
+    /// Indices into `UPGRADES`, in button order.
+    offers: Vec<usize>,
+    focus: usize,
+}
+
+#[derive(Component)]
+struct UpgradeButton(usize);
+
+fn reset_experience(mut experience: ResMut<Experience>) {
+    *experience = Experience::default();
+}
+
+fn gain_experience(
+    mut events: EventReader<EnemyKilledEvent>,
+    mut experience: ResMut<Experience>,
+) {
+    for event in events.read() {
+        experience.gain(event.points.max(0) as u32);
+    }
+}
+
+fn open_level_up(
+    mut commands: Commands,
+    mut experience: ResMut<Experience>,
+    player_query: Query<(&Dash, &Arsenal), With<Player>>,
+    state: Res<State<RunState>>,
+    mut next_state: ResMut<NextState<RunState>>,
+) {
+    if experience.pending == 0 {
+        return;
+    }
+
+    // Levels are shared, so an upgrade is offered while any player can still take it.
+    let available: Vec<_> = (0..UPGRADES.len())
+        .filter(|&index| player_query.iter().any(|(dash, arsenal)| UPGRADES[index].available(dash, arsenal)))
+        .collect();
+    let offers: Vec<_> = available
+        .choose_multiple_weighted(&mut rand::thread_rng(), CHOICES, |&index| UPGRADES[index].rarity.weight())
+        .map(|chosen| chosen.copied().collect())
+        .unwrap_or_default();
+    if offers.is_empty() {
+        // Nothing is left to offer, so the remaining level-ups pass without a choice.
+        experience.pending = 0;
+        if *state.get() == RunState::LevelUp {
+            next_state.set(RunState::Running);
+        }
+        return;
+    }
+
+    // Consecutive level-ups open one screen after another without resuming in between.
+    if *state.get() == RunState::Running {
+        next_state.set(RunState::LevelUp);
+    }
+    let root = commands
+        .spawn(NodeBundle {
+            style: Style {
+                position_type: PositionType::Absolute,
+                width: Val::Percent(100.),
+                height: Val::Percent(100.),
+                flex_direction: FlexDirection::Column,
+                justify_content: JustifyContent::Center,
+                align_items: AlignItems::Center,
+                row_gap: Val::Px(30.),
+                ..default()
+            },
+            background_color: Color::srgba(0., 0., 0., 0.6).into(),
+            z_index: ZIndex::Global(10),
+            ..default()
+        })
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(
+                format!("LEVEL {}", experience.level - experience.pending + 1),
+                TextStyle {
+                    font_size: 48.0,
+                    color: Color::srgb(0.9, 0.9, 0.9),
+                    ..default()
+                },
+            ));
+
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        column_gap: Val::Px(20.),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    for (slot, &index) in offers.iter().enumerate() {
+                        let upgrade = &UPGRADES[index];
+                        parent
+                            .spawn((ButtonBundle {
+                                style: Style {
+                                    width: Val::Px(220.),
+                                    height: Val::Px(120.),
+                                    flex_direction: FlexDirection::Column,
+                                    justify_content: JustifyContent::Center,
+                                    align_items: AlignItems::Center,
+                                    row_gap: Val::Px(10.),
+                                    padding: UiRect::all(Val::Px(10.)),
+                                    border: UiRect::all(Val::Px(3.)),
+                                    ..default()
+                                },
+                                border_color: upgrade.rarity.color().into(),
+                                image: UiImage::default().with_color(if slot == 0 { FOCUSED_BUTTON } else { NORMAL_BUTTON }),
+                                ..default()
+                            }, UpgradeButton(slot)))
+                            .with_children(|parent| {
+                                parent.spawn(TextBundle::from_section(upgrade.name, TextStyle {
+                                    font_size: 28.0,
+                                    color: upgrade.rarity.color(),
+                                    ..default()
+                                }));
+                                parent.spawn(TextBundle::from_section(upgrade.description, TextStyle {
+                                    font_size: 18.0,
+                                    color: Color::srgb(0.9, 0.9, 0.9),
+                                    ..default()
+                                }));
+                            });
+                    }
+                });
+        })
+        .id();
+    commands.insert_resource(LevelUpScreen { root, offers, focus: 0 });
+}
+
+fn choose_upgrade(
+    mut commands: Commands,
+    mut screen: ResMut<LevelUpScreen>,
+    mut experience: ResMut<Experience>,
+    actions: Res<ButtonInput<InputAction>>,
+    mut buttons_query: Query<(&Interaction, &UpgradeButton, &mut UiImage)>,
+    mut player_query: Query<(&mut Stats, &mut Dash, &mut Arsenal), With<Player>>,
+    mut next_state: ResMut<NextState<RunState>>,
+) {
+    let mut chosen = None;
+    for (interaction, button, _) in &buttons_query {
+        match interaction {
+            Interaction::Pressed => chosen = Some(button.0),
+            Interaction::Hovered => screen.focus = button.0,
+            Interaction::None => {}
+        }
+    }
+    let count = screen.offers.len();
+    if actions.just_pressed(InputAction::MoveRight) {
+        screen.focus = (screen.focus + 1) % count;
+    }
+    if actions.just_pressed(InputAction::MoveLeft) {
+        screen.focus = (screen.focus + count - 1) % count;
+    }
+    if actions.just_pressed(InputAction::Confirm) {
+        chosen = Some(screen.focus);
+    }
+    for (_, button, mut image) in &mut buttons_query {
+        image.color = if button.0 == screen.focus { FOCUSED_BUTTON } else { NORMAL_BUTTON };
+    }
+
+    let Some(slot) = chosen else {
+        return;
+    };
+    let upgrade = &UPGRADES[screen.offers[slot]];
+    for (mut stats, mut dash, mut arsenal) in &mut player_query {
+        if upgrade.available(&dash, &arsenal) {
+            upgrade.apply(&mut stats, &mut dash, &mut arsenal);
+        }
+    }
+    commands.entity(screen.root).despawn_recursive();
+    commands.remove_resource::<LevelUpScreen>();
+    experience.pending -= 1;
+    if experience.pending == 0 {
+        next_state.set(RunState::Running);
+    }
+}
+
+/// Removes a choice screen left open when the run stops being held for it.
+fn close_level_up(mut commands: Commands, screen: Option<Res<LevelUpScreen>>) {
+    if let Some(screen) = screen {
+        commands.entity(screen.root).despawn_recursive();
+        commands.remove_resource::<LevelUpScreen>();
+    }
+}